@@ -28,8 +28,7 @@ export const onPost: RequestHandler = async ({ exit, json, request, params }) =>
     const promises: Promise<any>[] = [];
     for (const symbol of Object.values(qManifest.symbols)) {
       const existing = existingMap.get(symbol.hash);
-      const lo = symbol.loc[0];
-      const hi = symbol.loc[1];
+      const { lo, hi } = symbol.loc;
       if (existing) {
         if (
           existing.fullName !== symbol.displayName ||
@@ -6,7 +6,10 @@ extern crate napi;
 #[macro_use]
 extern crate napi_derive;
 
-use napi::{CallContext, JsObject, JsUnknown, Result};
+use napi::{CallContext, Env, JsFunction, JsObject, JsUnknown, Result, Status};
+use qwik_core::TransformError;
+use serde::Serialize;
+use std::sync::mpsc;
 
 #[cfg(windows)]
 #[global_allocator]
@@ -18,8 +21,48 @@ fn transform_fs(ctx: CallContext) -> Result<JsUnknown> {
 	let opts = ctx.get::<JsObject>(0)?;
 	let config: qwik_core::TransformFsOptions = ctx.env.from_js_value(opts)?;
 
-	let result = qwik_core::transform_fs(config).unwrap();
-	ctx.env.to_js_value(&result)
+	to_js_result(ctx.env, qwik_core::transform_fs(config))
+}
+
+/// Like `transform_fs`, but calls the function given second with the output of each file as
+/// soon as it is transformed. The result has the diagnostics of every file, without the code.
+#[allow(clippy::needless_pass_by_value)]
+#[js_function(2)]
+fn transform_fs_streaming(ctx: CallContext) -> Result<JsUnknown> {
+	let opts = ctx.get::<JsObject>(0)?;
+	let on_output = ctx.get::<JsFunction>(1)?;
+	let config: qwik_core::TransformFsOptions = ctx.env.from_js_value(opts)?;
+
+	// The files are transformed on another thread, JS can only be called from this one. The
+	// transform waits when JS falls behind, at most a few outputs are held in memory.
+	let (sender, receiver) = mpsc::sync_channel(16);
+	let (result, called) = std::thread::scope(|scope| {
+		let transform = scope.spawn(move || {
+			qwik_core::transform_fs_streaming(config, &|output| {
+				// The receiver is dropped once the callback threw, which stops the transform
+				sender
+					.send(output)
+					.map_err(|_| TransformError::aborted("the output callback threw"))
+			})
+		});
+		let mut called = Ok(());
+		for output in receiver {
+			called = ctx
+				.env
+				.to_js_value(&output)
+				.and_then(|value| on_output.call(None, &[value]))
+				.map(|_| ());
+			if called.is_err() {
+				break;
+			}
+		}
+		let result = transform
+			.join()
+			.unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+		(result, called)
+	});
+	called?;
+	to_js_result(ctx.env, result)
 }
 
 #[allow(clippy::needless_pass_by_value)]
@@ -28,14 +71,45 @@ fn transform_modules(ctx: CallContext) -> Result<JsUnknown> {
 	let opts = ctx.get::<JsObject>(0)?;
 	let config: qwik_core::TransformModulesOptions = ctx.env.from_js_value(opts)?;
 
-	let result = qwik_core::transform_modules(config).unwrap();
-	ctx.env.to_js_value(&result)
+	to_js_result(ctx.env, qwik_core::transform_modules(config))
+}
+
+#[allow(clippy::needless_pass_by_value)]
+#[js_function(1)]
+fn analyze_modules(ctx: CallContext) -> Result<JsUnknown> {
+	let opts = ctx.get::<JsObject>(0)?;
+	let config: qwik_core::AnalyzeModulesOptions = ctx.env.from_js_value(opts)?;
+
+	to_js_result(ctx.env, qwik_core::analyze_modules(config))
+}
+
+/// Converts the result of a transform into its JS value, or throws a JS error carrying
+/// the `code` and `path` of the `TransformError`.
+fn to_js_result<T: Serialize>(
+	env: &Env,
+	result: std::result::Result<T, TransformError>,
+) -> Result<JsUnknown> {
+	match result {
+		Ok(output) => env.to_js_value(&output),
+		Err(err) => {
+			let mut error =
+				env.create_error(napi::Error::new(Status::GenericFailure, err.to_string()))?;
+			error.set_named_property("code", env.create_string(err.code())?)?;
+			if let Some(path) = err.path() {
+				error.set_named_property("path", env.create_string(&path.to_string_lossy())?)?;
+			}
+			env.throw(error)?;
+			Ok(env.get_undefined()?.into_unknown())
+		}
+	}
 }
 
 #[module_exports]
 fn init(mut exports: JsObject) -> Result<()> {
 	exports.create_named_method("transform_fs", transform_fs)?;
+	exports.create_named_method("transform_fs_streaming", transform_fs_streaming)?;
 	exports.create_named_method("transform_modules", transform_modules)?;
+	exports.create_named_method("analyze_modules", analyze_modules)?;
 
 	Ok(())
 }
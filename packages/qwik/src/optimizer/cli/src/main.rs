@@ -2,14 +2,23 @@
 #![deny(clippy::perf)]
 #![deny(clippy::nursery)]
 
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
 
 use clap::{Arg, Command};
+use notify::{RecursiveMode, Watcher};
 use path_absolutize::Absolutize;
-use qwik_core::{transform_fs, EmitMode, EntryStrategy, MinifyMode, TransformFsOptions};
+use qwik_core::{
+	transform_fs_streaming, transform_fs_with_cache, EmitMode, EntryStrategy, Globs, MinifyMode,
+	TransformCache, TransformError, TransformFsOptions, TransformModule, TransformOutput,
+};
 
 struct OptimizerInput {
-	glob: Option<String>,
+	glob: Option<Vec<String>>,
+	vendor_roots: Vec<String>,
 	manifest: Option<String>,
 	core_module: Option<String>,
 	scope: Option<String>,
@@ -22,10 +31,80 @@ struct OptimizerInput {
 	preserve_filenames: bool,
 	minify: MinifyMode,
 	sourcemaps: bool,
+	inline_sourcemaps: bool,
+	sources_content: bool,
+	sourcemap_file: String,
 	explicit_extensions: bool,
+	watch: bool,
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+#[derive(Debug)]
+enum CliError {
+	InvalidOption {
+		option: &'static str,
+		value: String,
+	},
+	Io {
+		path: PathBuf,
+		source: std::io::Error,
+	},
+	Transform(TransformError),
+	Watch(String),
+}
+
+impl CliError {
+	const fn exit_code(&self) -> i32 {
+		match self {
+			Self::InvalidOption { .. } | Self::Transform(TransformError::InvalidOption { .. }) => 2,
+			_ => 1,
+		}
+	}
+
+	fn io<P: Into<PathBuf>>(path: P) -> impl FnOnce(std::io::Error) -> Self {
+		let path = path.into();
+		move |source| Self::Io { path, source }
+	}
+}
+
+impl fmt::Display for CliError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::InvalidOption { option, value } => {
+				write!(f, "invalid value '{}' for --{}", value, option)
+			}
+			Self::Io { path, source } => write!(f, "{}: {}", path.to_string_lossy(), source),
+			Self::Transform(err) => write!(f, "[{}] {}", err.code(), err),
+			Self::Watch(reason) => write!(f, "watching files: {}", reason),
+		}
+	}
+}
+
+impl From<TransformError> for CliError {
+	fn from(err: TransformError) -> Self {
+		Self::Transform(err)
+	}
+}
+
+impl From<notify::Error> for CliError {
+	fn from(err: notify::Error) -> Self {
+		Self::Watch(err.to_string())
+	}
+}
+
+impl From<mpsc::RecvError> for CliError {
+	fn from(err: mpsc::RecvError) -> Self {
+		Self::Watch(err.to_string())
+	}
+}
+
+fn main() {
+	if let Err(err) = run() {
+		eprintln!("error: {}", err);
+		std::process::exit(err.exit_code());
+	}
+}
+
+fn run() -> Result<(), CliError> {
 	let matches = Command::new("qwik")
         .version("1.0")
         .arg_required_else_help(true)
@@ -57,7 +136,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .arg(
                     Arg::new("strategy")
                     .long("strategy")
-                        .possible_values(["inline","single", "hook", "smart", "component"])
+                        .possible_values(["inline","single", "hook", "smart", "component", "sized"])
                         .takes_value(true)
                         .help("entry strategy used to group hooks"),
                 )
@@ -81,7 +160,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                  .arg(Arg::new("preserve-filenames").long("preserve-filenames").help("preserves original filename").takes_value(false))
                 .arg(Arg::new("minify").long("minify").possible_values(["minify", "simplify", "none"]).takes_value(true).help("outputs minified source code"))
                 .arg(Arg::new("sourcemaps").long("sourcemaps").help("generates sourcemaps").takes_value(false))
-                .arg(Arg::new("extensions").long("extensions").help("keep explicit extensions on imports").takes_value(false)),
+                .arg(Arg::new("inline-sourcemaps").long("inline-sourcemaps").help("generates sourcemaps inlined in the source code").takes_value(false))
+                .arg(Arg::new("sources-content").long("sources-content").help("includes the original source code in the sourcemaps").takes_value(false))
+                .arg(Arg::new("sourcemap-file").long("sourcemap-file").takes_value(true).help("name of the sourcemap files, [file] is the name of the module, defaults to [file].map"))
+                .arg(Arg::new("extensions").long("extensions").help("keep explicit extensions on imports").takes_value(false))
+                .arg(Arg::new("mode").long("mode").possible_values(["dev", "prod", "lib"]).takes_value(true).help("emit mode"))
+                .arg(Arg::new("core_module").long("core-module").takes_value(true).help("module specifier of the qwik core package"))
+                .arg(Arg::new("scope").long("scope").takes_value(true).help("scope used to compute symbol hashes"))
+                .arg(
+                    Arg::new("vendor-root")
+                        .long("vendor-root")
+                        .takes_value(true)
+                        .multiple_occurrences(true)
+                        .help("additional directory of qwik code to optimize, can be repeated"),
+                )
+                .arg(
+                    Arg::new("glob")
+                        .short('g')
+                        .long("glob")
+                        .takes_value(true)
+                        .multiple_occurrences(true)
+                        .help("glob of the files to optimize, relative to the source directory, prefix with ! to exclude files, can be repeated"),
+                )
+                .arg(Arg::new("watch").short('w').long("watch").help("watches the source directories and optimizes files as they change").takes_value(false)),
         )
         .get_matches();
 
@@ -94,76 +195,273 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 			Some("hook") => EntryStrategy::Hook,
 			Some("single") => EntryStrategy::Single,
 			Some("component") => EntryStrategy::Component,
+			Some("sized") => EntryStrategy::Sized,
 			Some("smart") | None => EntryStrategy::Smart,
-			_ => panic!("Invalid strategy option"),
+			Some(value) => {
+				return Err(CliError::InvalidOption {
+					option: "strategy",
+					value: value.into(),
+				})
+			}
 		};
 
 		let minify = match matches.value_of("minify") {
+			Some("minify") => MinifyMode::Minify,
 			Some("none") => MinifyMode::None,
 			Some("simplify") | None => MinifyMode::Simplify,
-			_ => panic!("Invalid minify option"),
+			Some(value) => {
+				return Err(CliError::InvalidOption {
+					option: "minify",
+					value: value.into(),
+				})
+			}
 		};
 
 		let mode = match matches.value_of("mode") {
 			Some("dev") => EmitMode::Dev,
 			Some("prod") => EmitMode::Prod,
 			Some("lib") | None => EmitMode::Lib,
-			_ => panic!("Invalid mode option"),
+			Some(value) => {
+				return Err(CliError::InvalidOption {
+					option: "mode",
+					value: value.into(),
+				})
+			}
 		};
-		optimize(OptimizerInput {
+		let input = OptimizerInput {
 			src: matches.value_of_t_or_exit("src"),
 			dest: matches.value_of_t_or_exit("dest"),
 			manifest: matches.value_of("manifest").map(|s| s.into()),
 			core_module: matches.value_of("core_module").map(|s| s.into()),
 			scope: matches.value_of("scope").map(|s| s.into()),
 			mode,
-			glob: None,
+			glob: matches
+				.values_of("glob")
+				.map(|values| values.map(Into::into).collect()),
 			strategy,
 			minify,
 			explicit_extensions: matches.is_present("extensions"),
 			transpile_jsx: !matches.is_present("no-jsx"),
 			transpile_ts: !matches.is_present("no-ts"),
 			preserve_filenames: matches.is_present("preserve-filenames"),
-			sourcemaps: matches.is_present("sourcemaps"),
-		})?;
+			sourcemaps: matches.is_present("sourcemaps") || matches.is_present("inline-sourcemaps"),
+			inline_sourcemaps: matches.is_present("inline-sourcemaps"),
+			sources_content: matches.is_present("sources-content"),
+			sourcemap_file: matches
+				.value_of("sourcemap-file")
+				.unwrap_or("[file].map")
+				.into(),
+			vendor_roots: matches
+				.values_of("vendor-root")
+				.map(|values| values.map(Into::into).collect())
+				.unwrap_or_default(),
+			watch: matches.is_present("watch"),
+		};
+		if input.watch {
+			watch(&input)?;
+		} else {
+			optimize(&input)?;
+		}
 	}
 	Ok(())
 }
 
-fn optimize(
-	optimizer_input: OptimizerInput,
-) -> Result<qwik_core::TransformOutput, Box<dyn std::error::Error>> {
-	let current_dir = std::env::current_dir()?;
-	let src_dir = current_dir.join(optimizer_input.src).canonicalize()?;
+fn optimize(optimizer_input: &OptimizerInput) -> Result<qwik_core::TransformOutput, CliError> {
+	let dest = resolve_path(&optimizer_input.dest)?;
+	// Files are written as soon as they are transformed, only their metadata is kept
+	let result = transform_fs_streaming(transform_options(optimizer_input)?, &|output| {
+		output
+			.write_to_fs(&dest, None, Some(&optimizer_input.sourcemap_file))
+			.map(|_| ())
+	})?;
+	if let Some(manifest) = &optimizer_input.manifest {
+		result.write_manifest(&dest, manifest)?;
+	}
+	Ok(result)
+}
+
+fn current_dir() -> Result<PathBuf, CliError> {
+	std::env::current_dir().map_err(CliError::io("."))
+}
+
+/// Resolves `path` relative to the current directory, it does not need to exist.
+fn resolve_path(path: &Path) -> Result<PathBuf, CliError> {
+	let path = current_dir()?.join(path);
+	path.absolutize()
+		.map(|path| path.to_path_buf())
+		.map_err(CliError::io(&path))
+}
+
+/// Resolves `path` relative to the current directory, it must exist.
+fn canonicalize_path(path: &Path) -> Result<PathBuf, CliError> {
+	let path = current_dir()?.join(path);
+	path.canonicalize().map_err(CliError::io(&path))
+}
 
-	let result = transform_fs(TransformFsOptions {
+fn transform_options(optimizer_input: &OptimizerInput) -> Result<TransformFsOptions, CliError> {
+	let src_dir = canonicalize_path(&optimizer_input.src)?;
+
+	Ok(TransformFsOptions {
 		src_dir: src_dir.to_string_lossy().to_string(),
-		vendor_roots: vec![],
-		glob: optimizer_input.glob,
+		vendor_roots: optimizer_input.vendor_roots.clone(),
+		glob: optimizer_input.glob.clone().map(Globs::Many),
 		source_maps: optimizer_input.sourcemaps,
+		inline_source_maps: optimizer_input.inline_sourcemaps,
+		sources_content: optimizer_input.sources_content,
 		minify: optimizer_input.minify,
 		transpile_jsx: optimizer_input.transpile_jsx,
 		transpile_ts: optimizer_input.transpile_ts,
 		preserve_filenames: optimizer_input.preserve_filenames,
 		entry_strategy: optimizer_input.strategy,
 		explicit_extensions: optimizer_input.explicit_extensions,
-		core_module: optimizer_input.core_module,
+		core_module: optimizer_input.core_module.clone(),
 		root_dir: None,
 
 		mode: optimizer_input.mode,
-		scope: optimizer_input.scope,
+		scope: optimizer_input.scope.clone(),
 
 		manual_chunks: None,
+
+		sized_chunks: None,
+
+		symbol_traces: None,
+
+		entry_rules: None,
+		es_target: None,
+		jsx_import_source: None,
+		jsx_pragma: None,
+		jsx_pragma_frag: None,
+		define: None,
 		strip_exports: None,
 		strip_ctx_name: None,
 		strip_event_handlers: false,
 		reg_ctx_name: None,
 		is_server: None,
-	})?;
+	})
+}
 
-	result.write_to_fs(
-		&current_dir.join(optimizer_input.dest).absolutize()?,
-		optimizer_input.manifest,
-	)?;
-	Ok(result)
+/// Optimizes the sources once, then keeps watching the source directories. Every change
+/// only re-transforms the files that changed, since unchanged files are served by the
+/// transform cache, and only the outputs that changed are written to `dest`.
+fn watch(optimizer_input: &OptimizerInput) -> Result<(), CliError> {
+	let dest = resolve_path(&optimizer_input.dest)?;
+	let cache = TransformCache::new();
+
+	let (tx, rx) = mpsc::channel();
+	let mut watcher = notify::recommended_watcher(tx)?;
+	let mut roots = vec![canonicalize_path(&optimizer_input.src)?];
+	for root in &optimizer_input.vendor_roots {
+		roots.push(canonicalize_path(Path::new(root))?);
+	}
+	for root in &roots {
+		watcher.watch(root, RecursiveMode::Recursive)?;
+	}
+
+	let mut previous = TransformOutput::new();
+	loop {
+		match transform_fs_with_cache(transform_options(optimizer_input)?, &cache) {
+			Ok(result) => {
+				let written =
+					write_changes(&previous, &result, &dest, &optimizer_input.sourcemap_file)?;
+				if let Some(manifest) = &optimizer_input.manifest {
+					result.write_manifest(&dest, manifest)?;
+				}
+				print_diagnostics(&result);
+				println!(
+					"Optimized {} modules, {} written to {}",
+					result.modules.len(),
+					written,
+					dest.to_string_lossy()
+				);
+				previous = result;
+			}
+			Err(err) => eprintln!("error: {}", CliError::from(err)),
+		}
+
+		// Wait for a relevant change, then for the file system to settle
+		loop {
+			let event = rx.recv()?;
+			if is_relevant_event(&event, &dest) {
+				break;
+			}
+		}
+		while rx.recv_timeout(Duration::from_millis(50)).is_ok() {}
+	}
+}
+
+fn is_relevant_event(event: &notify::Result<notify::Event>, dest: &Path) -> bool {
+	event.as_ref().map_or(true, |event| {
+		!event.kind.is_access() && event.paths.iter().any(|path| !path.starts_with(dest))
+	})
+}
+
+/// Writes the modules whose code or source map changed since `previous`, and removes the
+/// files of the modules that are gone.
+fn write_changes(
+	previous: &TransformOutput,
+	result: &TransformOutput,
+	dest: &Path,
+	sourcemap_file: &str,
+) -> Result<usize, CliError> {
+	let previous_modules: HashMap<&str, &TransformModule> = previous
+		.modules
+		.iter()
+		.map(|module| (module.path.as_str(), module))
+		.collect();
+	let changed = TransformOutput {
+		modules: result
+			.modules
+			.iter()
+			.filter(|module| {
+				previous_modules
+					.get(module.path.as_str())
+					.map_or(true, |previous| {
+						previous.code != module.code || previous.map != module.map
+					})
+			})
+			.cloned()
+			.collect(),
+		..TransformOutput::new()
+	};
+	let written = changed.write_to_fs(dest, None, Some(sourcemap_file))?;
+
+	let current_paths: HashSet<&str> = result
+		.modules
+		.iter()
+		.map(|module| module.path.as_str())
+		.collect();
+	for (path, module) in &previous_modules {
+		if current_paths.contains(path) {
+			continue;
+		}
+		let remove_path = dest.join(path);
+		let mut remove_paths = vec![remove_path.clone()];
+		if module.map.is_some() {
+			if let Some(parent) = remove_path.parent() {
+				remove_paths.push(parent.join(module.source_map_file(sourcemap_file)));
+			}
+		}
+		for remove_path in remove_paths {
+			match std::fs::remove_file(&remove_path) {
+				Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+					return Err(CliError::io(remove_path)(err))
+				}
+				_ => {}
+			}
+		}
+	}
+	Ok(written)
+}
+
+fn print_diagnostics(result: &TransformOutput) {
+	for diagnostic in &result.diagnostics {
+		let code = diagnostic
+			.code
+			.as_ref()
+			.map_or_else(String::new, |code| format!(" [{}]", code));
+		eprintln!(
+			"{:?}{}: {}: {}",
+			diagnostic.category, code, diagnostic.file, diagnostic.message
+		);
+	}
 }
@@ -185,20 +185,33 @@ fn transform_todo_app(b: &mut Bencher) {
       "#;
       transform_modules(TransformModulesOptions {
         src_dir: "/user/qwik/src/".into(),
+          root_dir: None,
           input: vec![TransformModuleInput {
               code: code.into(),
               path: "file.tsx".into(),
+              map: None,
           }],
           source_maps: false,
+          inline_source_maps: false,
+          sources_content: false,
           explicit_extensions: false,
           minify: MinifyMode::Simplify,
           transpile_ts: true,
           transpile_jsx: true,
           preserve_filenames: false,
           manual_chunks: None,
+          sized_chunks: None,
+          symbol_traces: None,
+          entry_rules: None,
+          es_target: None,
+          jsx_import_source: None,
+          jsx_pragma: None,
+          jsx_pragma_frag: None,
+          define: None,
           entry_strategy: EntryStrategy::Single,
           mode: EmitMode::Prod,
           scope: None,
+          core_module: None,
           reg_ctx_name: None,
           strip_exports: None,
           strip_ctx_name: None,
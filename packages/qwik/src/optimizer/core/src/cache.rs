@@ -0,0 +1,197 @@
+use crate::parse::TransformOutput;
+
+use crate::errors::TransformError;
+use crate::hash::{StableHasher, HASH_VERSION};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+
+const OPTIMIZER_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Bump when the output of the transforms changes without a new optimizer version, so the
+/// entries written by the previous builds are not used.
+const CACHE_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+	pub hits: usize,
+	pub misses: usize,
+}
+
+/// Stores the output of every transformed module, so unchanged modules are not parsed and
+/// transformed again. There is one entry per module path and transform options, it is valid
+/// while the source code and the optimizer version are the same, and a changed module replaces
+/// it. Entries are kept in memory and, when a directory is provided, persisted to disk so they
+/// survive process restarts.
+#[derive(Default)]
+pub struct TransformCache {
+	/// The key of the transformed content and its output, by slot.
+	entries: Mutex<HashMap<u64, (u64, TransformOutput)>>,
+	#[cfg(feature = "fs")]
+	dir: Option<PathBuf>,
+	hits: AtomicUsize,
+	misses: AtomicUsize,
+}
+
+#[cfg(feature = "fs")]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheEntry {
+	key: u64,
+	output: TransformOutput,
+	// `TransformModule::order` is not serialized, keep it alongside the output
+	orders: Vec<u64>,
+}
+
+impl TransformCache {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	#[cfg(feature = "fs")]
+	pub fn with_dir<P: AsRef<Path>>(dir: P) -> Result<Self, TransformError> {
+		let dir = dir.as_ref();
+		fs::create_dir_all(dir).map_err(|err| TransformError::io(dir, err))?;
+		Ok(Self {
+			dir: Some(dir.to_path_buf()),
+			..Self::default()
+		})
+	}
+
+	pub fn stats(&self) -> CacheStats {
+		CacheStats {
+			hits: self.hits.load(Ordering::Relaxed),
+			misses: self.misses.load(Ordering::Relaxed),
+		}
+	}
+
+	pub fn clear(&self) {
+		self.entries().clear();
+		self.hits.store(0, Ordering::Relaxed);
+		self.misses.store(0, Ordering::Relaxed);
+	}
+
+	/// The entries in memory. A transform that panicked while holding the lock leaves them
+	/// usable, every slot is replaced at once.
+	fn entries(&self) -> MutexGuard<'_, HashMap<u64, (u64, TransformOutput)>> {
+		self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+
+	pub(crate) fn get_or_insert_with<F>(
+		&self,
+		options_key: &str,
+		path: &str,
+		code: &str,
+		input_map: Option<&str>,
+		transform: F,
+	) -> Result<TransformOutput, TransformError>
+	where
+		F: FnOnce() -> Result<TransformOutput, TransformError>,
+	{
+		let slot = compute_slot(options_key, path);
+		let key = compute_key(code, input_map);
+		if let Some(output) = self.get(slot, key) {
+			self.hits.fetch_add(1, Ordering::Relaxed);
+			return Ok(output);
+		}
+		self.misses.fetch_add(1, Ordering::Relaxed);
+		let output = transform()?;
+		self.insert(slot, key, &output)?;
+		Ok(output)
+	}
+
+	fn get(&self, slot: u64, key: u64) -> Option<TransformOutput> {
+		if let Some((entry_key, output)) = self.entries().get(&slot) {
+			if *entry_key == key {
+				return Some(output.clone());
+			}
+		}
+		#[cfg(feature = "fs")]
+		if let Some(output) = self.read_entry(slot, key) {
+			self.entries().insert(slot, (key, output.clone()));
+			return Some(output);
+		}
+		None
+	}
+
+	fn insert(&self, slot: u64, key: u64, output: &TransformOutput) -> Result<(), TransformError> {
+		#[cfg(feature = "fs")]
+		self.write_entry(slot, key, output)?;
+		self.entries().insert(slot, (key, output.clone()));
+		Ok(())
+	}
+
+	#[cfg(feature = "fs")]
+	fn entry_path(&self, slot: u64) -> Option<PathBuf> {
+		self.dir
+			.as_ref()
+			.map(|dir| dir.join(format!("{:016x}.json", slot)))
+	}
+
+	#[cfg(feature = "fs")]
+	fn read_entry(&self, slot: u64, key: u64) -> Option<TransformOutput> {
+		let path = self.entry_path(slot)?;
+		// A missing, outdated or corrupted entry is just a cache miss
+		let json = fs::read_to_string(path).ok()?;
+		let entry: CacheEntry = serde_json::from_str(&json).ok()?;
+		let mut output = entry.output;
+		if entry.key != key || output.modules.len() != entry.orders.len() {
+			return None;
+		}
+		for (module, order) in output.modules.iter_mut().zip(entry.orders) {
+			module.order = order;
+		}
+		Some(output)
+	}
+
+	#[cfg(feature = "fs")]
+	fn write_entry(
+		&self,
+		slot: u64,
+		key: u64,
+		output: &TransformOutput,
+	) -> Result<(), TransformError> {
+		if let Some(path) = self.entry_path(slot) {
+			let entry = CacheEntry {
+				key,
+				output: output.clone(),
+				orders: output.modules.iter().map(|m| m.order).collect(),
+			};
+			let json = serde_json::to_string(&entry)
+				.map_err(|err| TransformError::io(&path, err.into()))?;
+			fs::write(&path, json).map_err(|err| TransformError::io(&path, err))?;
+		}
+		Ok(())
+	}
+}
+
+/// Identifies the module an entry is stored for, by its path and the transform options.
+fn compute_slot(options_key: &str, path: &str) -> u64 {
+	let mut hasher = StableHasher::new();
+	hasher.write(options_key.as_bytes());
+	hasher.write_u8(0);
+	hasher.write(path.as_bytes());
+	hasher.finish()
+}
+
+/// Identifies what the output of an entry was transformed from, and by which optimizer.
+fn compute_key(code: &str, input_map: Option<&str>) -> u64 {
+	let mut hasher = StableHasher::new();
+	hasher.write(OPTIMIZER_VERSION.as_bytes());
+	hasher.write_u8(0);
+	hasher.write(&HASH_VERSION.to_le_bytes());
+	hasher.write(&CACHE_VERSION.to_le_bytes());
+	hasher.write(code.as_bytes());
+	if let Some(input_map) = input_map {
+		hasher.write_u8(0);
+		hasher.write(input_map.as_bytes());
+	}
+	hasher.finish()
+}
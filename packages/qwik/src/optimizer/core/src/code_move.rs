@@ -1,7 +1,10 @@
-use crate::collector::{new_ident_from_id, GlobalCollect, Id, ImportKind};
+use crate::collector::{
+	collect_relative_imports, new_ident_from_id, GlobalCollect, Id, ImportKind,
+};
+use crate::errors::TransformError;
 use crate::parse::{
-	emit_source_code, might_need_handle_watch, HookAnalysis, PathData, TransformModule,
-	TransformOutput,
+	emit_source_code, might_need_handle_watch, HookAnalysis, PathData, SourceMapOptions,
+	TransformModule, TransformOutput,
 };
 use crate::transform::{add_handle_watch, create_synthetic_named_import};
 use crate::words::*;
@@ -9,7 +12,7 @@ use crate::words::*;
 use std::collections::BTreeMap;
 use std::path::Path;
 
-use anyhow::{Context, Error};
+use anyhow::Error;
 use path_slash::PathExt;
 use swc_atoms::JsWord;
 use swc_common::comments::{SingleThreadedComments, SingleThreadedCommentsMap};
@@ -224,8 +227,8 @@ pub fn generate_entries(
 	mut output: TransformOutput,
 	core_module: &JsWord,
 	explicit_extensions: bool,
-	root_dir: Option<&Path>,
-) -> Result<TransformOutput, anyhow::Error> {
+	source_maps: Option<&SourceMapOptions>,
+) -> Result<TransformOutput, TransformError> {
 	let source_map = Lrc::new(SourceMap::default());
 	let mut entries_map: BTreeMap<&str, Vec<&HookAnalysis>> = BTreeMap::new();
 	let mut new_modules = Vec::with_capacity(output.modules.len());
@@ -242,16 +245,28 @@ pub fn generate_entries(
 
 		for (entry, hooks) in &entries_map {
 			let module = new_entry_module(entry, hooks, core_module, explicit_extensions);
-			let (code, map) =
-				emit_source_code(Lrc::clone(&source_map), None, &module, root_dir, false)
-					.context("Emitting source code")?;
+			let path = [entry, ".js"].concat();
+			let (code, map) = emit_source_code(
+				Lrc::clone(&source_map),
+				None,
+				&module,
+				source_maps,
+				false,
+				ast::EsVersion::latest(),
+			)
+			.map_err(|err| TransformError::emit(&path, err))?;
+			let imports = collect_relative_imports(&module);
 			new_modules.push(TransformModule {
-				path: [entry, ".js"].concat(),
+				path,
 				code,
 				map,
 				is_entry: true,
 				hook: None,
 				order: 0,
+				orig_path: None,
+				imports: imports.imports,
+				dynamic_imports: imports.dynamic_imports,
+				code_len: None,
 			});
 		}
 	}
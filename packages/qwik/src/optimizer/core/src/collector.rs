@@ -74,6 +74,12 @@ impl GlobalCollect {
 			.map(|s| s.0.clone())
 	}
 
+	/// Finds the import binding `local`, the root of a pragma like `React.createElement`.
+	pub fn get_import_by_local(&self, local: &str) -> Option<Id> {
+		let root = local.split('.').next().unwrap_or(local);
+		self.imports.keys().find(|id| &*id.0 == root).cloned()
+	}
+
 	pub fn is_global(&self, local: &Id) -> bool {
 		if self.imports.contains_key(local) {
 			return true;
@@ -388,6 +394,86 @@ impl Visit for IdentCollector {
 	}
 }
 
+/// Collects the relative specifiers of the modules imported by a module, statically with imports
+/// and re-exports, and dynamically with `import()`.
+#[derive(Debug, Default)]
+pub struct ImportsCollector {
+	pub imports: Vec<JsWord>,
+	pub dynamic_imports: Vec<JsWord>,
+}
+
+pub fn collect_relative_imports(module: &ast::Module) -> ImportsCollector {
+	let mut collector = ImportsCollector::default();
+	module.visit_with(&mut collector);
+	for specifiers in [&mut collector.imports, &mut collector.dynamic_imports] {
+		specifiers.retain(|specifier| specifier.starts_with('.'));
+		specifiers.sort();
+		specifiers.dedup();
+	}
+	collector
+}
+
+impl Visit for ImportsCollector {
+	noop_visit_type!();
+
+	fn visit_import_decl(&mut self, node: &ast::ImportDecl) {
+		self.imports.push(node.src.value.clone());
+	}
+
+	fn visit_named_export(&mut self, node: &ast::NamedExport) {
+		if let Some(src) = &node.src {
+			self.imports.push(src.value.clone());
+		}
+	}
+
+	fn visit_export_all(&mut self, node: &ast::ExportAll) {
+		self.imports.push(node.src.value.clone());
+	}
+
+	fn visit_call_expr(&mut self, node: &ast::CallExpr) {
+		// QRLs created by the transform call `import` as an identifier
+		let is_import = match &node.callee {
+			ast::Callee::Import(_) => true,
+			ast::Callee::Expr(box ast::Expr::Ident(ident)) => ident.sym == js_word!("import"),
+			ast::Callee::Expr(_) | ast::Callee::Super(_) => false,
+		};
+		if is_import {
+			if let Some(ast::ExprOrSpread {
+				expr: box ast::Expr::Lit(ast::Lit::Str(src)),
+				..
+			}) = node.args.first()
+			{
+				self.dynamic_imports.push(src.value.clone());
+			}
+		}
+		node.visit_children_with(self);
+	}
+}
+
+/// Collects the spans where variables, functions and classes are declared.
+#[derive(Debug, Default)]
+pub struct BindingCollector {
+	pub spans: HashMap<Id, Span>,
+}
+
+impl Visit for BindingCollector {
+	noop_visit_type!();
+
+	fn visit_binding_ident(&mut self, node: &ast::BindingIdent) {
+		self.spans.insert(id!(node.id), node.id.span);
+	}
+
+	fn visit_fn_decl(&mut self, node: &ast::FnDecl) {
+		self.spans.insert(id!(node.ident), node.ident.span);
+		node.visit_children_with(self);
+	}
+
+	fn visit_class_decl(&mut self, node: &ast::ClassDecl) {
+		self.spans.insert(id!(node.ident), node.ident.span);
+		node.visit_children_with(self);
+	}
+}
+
 pub fn collect_from_pat(pat: &ast::Pat, identifiers: &mut Vec<(Id, Span)>) -> bool {
 	match pat {
 		ast::Pat::Ident(ident) => {
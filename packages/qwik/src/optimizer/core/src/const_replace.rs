@@ -1,7 +1,15 @@
+use std::collections::HashMap;
+
 use crate::collector::{GlobalCollect, Id};
+use crate::errors::TransformError;
 use crate::words::*;
-use swc_common::DUMMY_SP;
+use serde_json::Value;
+use swc_atoms::JsWord;
+use swc_common::util::take::Take;
+use swc_common::{Mark, SyntaxContext, DUMMY_SP};
 use swc_ecmascript::ast;
+use swc_ecmascript::transforms::optimization::simplify;
+use swc_ecmascript::utils::{ExprCtx, ExprExt, Value::Known};
 use swc_ecmascript::visit::{VisitMut, VisitMutWith};
 pub struct ConstReplacerVisitor {
 	pub is_server: bool,
@@ -12,15 +20,19 @@ pub struct ConstReplacerVisitor {
 }
 
 impl ConstReplacerVisitor {
-	pub fn new(is_server: bool, is_dev: bool, global_collector: &GlobalCollect) -> Self {
+	pub fn new(
+		is_server: bool,
+		is_dev: bool,
+		global_collector: &GlobalCollect,
+		core_module: &JsWord,
+	) -> Self {
+		let build_module = JsWord::from([core_module, BUILD_SUBMODULE].concat());
 		Self {
 			is_server,
 			is_dev,
-			is_server_ident: global_collector
-				.get_imported_local(&IS_SERVER, &BUILDER_IO_QWIK_BUILD),
-			is_browser_ident: global_collector
-				.get_imported_local(&IS_BROWSER, &BUILDER_IO_QWIK_BUILD),
-			is_dev_ident: global_collector.get_imported_local(&IS_DEV, &BUILDER_IO_QWIK_BUILD),
+			is_server_ident: global_collector.get_imported_local(&IS_SERVER, &build_module),
+			is_browser_ident: global_collector.get_imported_local(&IS_BROWSER, &build_module),
+			is_dev_ident: global_collector.get_imported_local(&IS_DEV, &build_module),
 		}
 	}
 }
@@ -81,3 +93,323 @@ impl VisitMut for ConstReplacerVisitor {
 		}
 	}
 }
+
+/// The parsed `define` option: global identifiers or member paths like `process.env.NODE_ENV`,
+/// and imports written `<source>:<specifier>`, each replaced by a JSON value.
+#[derive(Debug, Default)]
+pub struct Defines {
+	globals: Vec<(Vec<JsWord>, Value)>,
+	imports: Vec<(JsWord, JsWord, Value)>,
+}
+
+impl Defines {
+	pub fn new(define: &HashMap<String, Value>) -> Result<Self, TransformError> {
+		let mut defines = Self::default();
+		for (key, value) in define {
+			if let Some((source, specifier)) = key.rsplit_once(':') {
+				if source.is_empty() || !is_ident(specifier) {
+					return Err(TransformError::invalid_option(
+						"define",
+						format!("{key:?} is not an import, expected <source>:<specifier>"),
+					));
+				}
+				defines
+					.imports
+					.push((source.into(), specifier.into(), value.clone()));
+			} else {
+				let path: Vec<JsWord> = key.split('.').map(JsWord::from).collect();
+				if !path.iter().all(|segment| is_ident(segment)) {
+					return Err(TransformError::invalid_option(
+						"define",
+						format!("{key:?} is not an identifier or a member path"),
+					));
+				}
+				defines.globals.push((path, value.clone()));
+			}
+		}
+		Ok(defines)
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.globals.is_empty() && self.imports.is_empty()
+	}
+}
+
+fn is_ident(word: &str) -> bool {
+	let mut chars = word.chars();
+	chars
+		.next()
+		.map_or(false, |c| c.is_alphabetic() || c == '_' || c == '$')
+		&& chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Replaces the identifiers of the `define` option with their values, the imported ones are
+/// found through the global collect and the global ones must not be declared in the module.
+/// The `if`, ternary and logical conditions that become constant drop their dead branch, the
+/// rest of the code is left as written.
+pub struct DefineReplacer<'a> {
+	globals: &'a [(Vec<JsWord>, Value)],
+	imports: Vec<(Id, &'a Value)>,
+	unresolved_mark: Mark,
+	did_replace: bool,
+}
+
+impl<'a> DefineReplacer<'a> {
+	pub fn new(
+		defines: &'a Defines,
+		global_collector: &GlobalCollect,
+		unresolved_mark: Mark,
+	) -> Self {
+		Self {
+			globals: &defines.globals,
+			imports: defines
+				.imports
+				.iter()
+				.filter_map(|(source, specifier, value)| {
+					let id = global_collector.get_imported_local(specifier, source)?;
+					Some((id, value))
+				})
+				.collect(),
+			unresolved_mark,
+			did_replace: false,
+		}
+	}
+
+	/// Replaces the defines in `test`, returns its value when it is constant because of them.
+	fn visit_mut_condition(&mut self, test: &mut ast::Expr) -> Option<bool> {
+		let did_replace = std::mem::replace(&mut self.did_replace, false);
+		test.visit_mut_with(self);
+		let value = if self.did_replace {
+			test.visit_mut_with(&mut simplify::expr_simplifier(
+				self.unresolved_mark,
+				Default::default(),
+			));
+			let ctx = ExprCtx {
+				unresolved_ctxt: SyntaxContext::empty().apply_mark(self.unresolved_mark),
+				is_unresolved_ref_safe: false,
+			};
+			match test.as_pure_bool(&ctx) {
+				Known(value) => Some(value),
+				_ => None,
+			}
+		} else {
+			None
+		};
+		self.did_replace |= did_replace;
+		value
+	}
+
+	/// Visits an assignment target, a bare identifier is written so it is not replaced.
+	fn visit_mut_target(&mut self, node: &mut ast::Expr) {
+		match node {
+			ast::Expr::Ident(_) => {}
+			ast::Expr::Paren(paren) => self.visit_mut_target(&mut paren.expr),
+			_ => node.visit_mut_children_with(self),
+		}
+	}
+
+	fn lookup(&self, expr: &ast::Expr) -> Option<&'a Value> {
+		let mut path = vec![];
+		let mut expr = expr;
+		let ident = loop {
+			match expr {
+				ast::Expr::Ident(ident) => break ident,
+				ast::Expr::Member(member) => {
+					path.push(match &member.prop {
+						ast::MemberProp::Ident(prop) => &prop.sym,
+						ast::MemberProp::Computed(ast::ComputedPropName {
+							expr: box ast::Expr::Lit(ast::Lit::Str(prop)),
+							..
+						}) => &prop.value,
+						_ => return None,
+					});
+					expr = &member.obj;
+				}
+				_ => return None,
+			}
+		};
+		if path.is_empty() {
+			let import = self
+				.imports
+				.iter()
+				.find(|(id, _)| id.0 == ident.sym && id.1 == ident.span.ctxt());
+			if let Some((_, value)) = import {
+				return Some(value);
+			}
+		}
+		if ident.span.ctxt.outer() != self.unresolved_mark {
+			return None;
+		}
+		path.push(&ident.sym);
+		path.reverse();
+		self.globals
+			.iter()
+			.find(|(global, _)| global.iter().eq(path.iter().copied()))
+			.map(|(_, value)| value)
+	}
+}
+
+impl<'a> VisitMut for DefineReplacer<'a> {
+	fn visit_mut_expr(&mut self, node: &mut ast::Expr) {
+		if let Some(value) = self.lookup(node) {
+			*node = json_to_expr(value);
+			self.did_replace = true;
+			return;
+		}
+		let branch = match node {
+			ast::Expr::Cond(cond) => match self.visit_mut_condition(&mut cond.test) {
+				Some(true) => cond.cons.take(),
+				Some(false) => cond.alt.take(),
+				None => {
+					cond.cons.visit_mut_with(self);
+					cond.alt.visit_mut_with(self);
+					return;
+				}
+			},
+			ast::Expr::Bin(
+				bin @ ast::BinExpr {
+					op: ast::BinaryOp::LogicalAnd | ast::BinaryOp::LogicalOr,
+					..
+				},
+			) => match self.visit_mut_condition(&mut bin.left) {
+				Some(value) if value == (bin.op == ast::BinaryOp::LogicalAnd) => bin.right.take(),
+				Some(_) => bin.left.take(),
+				None => {
+					bin.right.visit_mut_with(self);
+					return;
+				}
+			},
+			_ => {
+				node.visit_mut_children_with(self);
+				return;
+			}
+		};
+		*node = *branch;
+		node.visit_mut_with(self);
+	}
+
+	fn visit_mut_stmt(&mut self, node: &mut ast::Stmt) {
+		let ast::Stmt::If(if_stmt) = node else {
+			node.visit_mut_children_with(self);
+			return;
+		};
+		let branch = match self.visit_mut_condition(&mut if_stmt.test) {
+			Some(true) => *if_stmt.cons.take(),
+			Some(false) => if_stmt
+				.alt
+				.take()
+				.map_or(ast::Stmt::Empty(ast::EmptyStmt { span: DUMMY_SP }), |alt| {
+					*alt
+				}),
+			None => {
+				if_stmt.cons.visit_mut_with(self);
+				if_stmt.alt.visit_mut_with(self);
+				return;
+			}
+		};
+		*node = branch;
+		node.visit_mut_with(self);
+	}
+
+	fn visit_mut_assign_expr(&mut self, node: &mut ast::AssignExpr) {
+		// The defined identifiers are only replaced when read
+		match &mut node.left {
+			ast::PatOrExpr::Pat(pat) => pat.visit_mut_with(self),
+			ast::PatOrExpr::Expr(expr) => self.visit_mut_target(expr),
+		}
+		node.right.visit_mut_with(self);
+	}
+
+	fn visit_mut_pat(&mut self, node: &mut ast::Pat) {
+		// Expressions in patterns are assignment targets, like `[FLAG] = values`
+		if let ast::Pat::Expr(expr) = node {
+			self.visit_mut_target(expr);
+		} else {
+			node.visit_mut_children_with(self);
+		}
+	}
+
+	fn visit_mut_update_expr(&mut self, node: &mut ast::UpdateExpr) {
+		// `FLAG++` writes the defined identifier, only the reads around it are replaced
+		self.visit_mut_target(&mut node.arg);
+	}
+
+	fn visit_mut_unary_expr(&mut self, node: &mut ast::UnaryExpr) {
+		if node.op != ast::UnaryOp::Delete {
+			node.visit_mut_children_with(self);
+		}
+	}
+
+	fn visit_mut_prop(&mut self, node: &mut ast::Prop) {
+		if let ast::Prop::Shorthand(ident) = node {
+			if let Some(value) = self.lookup(&ast::Expr::Ident(ident.clone())) {
+				*node = ast::Prop::KeyValue(ast::KeyValueProp {
+					key: ast::PropName::Ident(ident.clone()),
+					value: Box::new(json_to_expr(value)),
+				});
+				return;
+			}
+		}
+		node.visit_mut_children_with(self);
+	}
+}
+
+fn json_to_expr(value: &Value) -> ast::Expr {
+	match value {
+		Value::Null => ast::Expr::Lit(ast::Lit::Null(ast::Null { span: DUMMY_SP })),
+		Value::Bool(value) => ast::Expr::Lit(ast::Lit::Bool(ast::Bool {
+			span: DUMMY_SP,
+			value: *value,
+		})),
+		Value::Number(number) => {
+			let number = number.as_f64().unwrap_or(f64::NAN);
+			let literal = ast::Expr::Lit(ast::Lit::Num(ast::Number {
+				span: DUMMY_SP,
+				value: number.abs(),
+				raw: None,
+			}));
+			if number.is_sign_negative() {
+				ast::Expr::Unary(ast::UnaryExpr {
+					span: DUMMY_SP,
+					op: ast::UnaryOp::Minus,
+					arg: Box::new(literal),
+				})
+			} else {
+				literal
+			}
+		}
+		Value::String(value) => ast::Expr::Lit(ast::Lit::Str(ast::Str {
+			span: DUMMY_SP,
+			value: value.as_str().into(),
+			raw: None,
+		})),
+		Value::Array(items) => ast::Expr::Array(ast::ArrayLit {
+			span: DUMMY_SP,
+			elems: items
+				.iter()
+				.map(|item| {
+					Some(ast::ExprOrSpread {
+						spread: None,
+						expr: Box::new(json_to_expr(item)),
+					})
+				})
+				.collect(),
+		}),
+		Value::Object(props) => ast::Expr::Object(ast::ObjectLit {
+			span: DUMMY_SP,
+			props: props
+				.iter()
+				.map(|(key, value)| {
+					ast::PropOrSpread::Prop(Box::new(ast::Prop::KeyValue(ast::KeyValueProp {
+						key: ast::PropName::Str(ast::Str {
+							span: DUMMY_SP,
+							value: key.as_str().into(),
+							raw: None,
+						}),
+						value: Box::new(json_to_expr(value)),
+					})))
+				})
+				.collect(),
+		}),
+	}
+}
@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use swc_common::comments::SingleThreadedComments;
+use swc_common::Mark;
+use swc_ecmascript::ast;
+use swc_ecmascript::ast::EsVersion;
+use swc_ecmascript::transforms::compat::{
+	es2015, es2016, es2017, es2018, es2019, es2020, es2021, es2022,
+};
+use swc_ecmascript::transforms::helpers::{inject_helpers, Helpers, HELPERS};
+use swc_ecmascript::visit::FoldWith;
+
+/// The ECMAScript version of the emitted code, older targets need a full compat pipeline.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EsTarget {
+	Es5,
+	Es2015,
+	Es2016,
+	Es2017,
+	Es2018,
+	Es2019,
+	Es2020,
+	Es2021,
+	Es2022,
+	EsNext,
+}
+
+impl From<EsTarget> for EsVersion {
+	fn from(target: EsTarget) -> Self {
+		match target {
+			EsTarget::Es5 => Self::Es5,
+			EsTarget::Es2015 => Self::Es2015,
+			EsTarget::Es2016 => Self::Es2016,
+			EsTarget::Es2017 => Self::Es2017,
+			EsTarget::Es2018 => Self::Es2018,
+			EsTarget::Es2019 => Self::Es2019,
+			EsTarget::Es2020 => Self::Es2020,
+			EsTarget::Es2021 => Self::Es2021,
+			EsTarget::Es2022 => Self::Es2022,
+			EsTarget::EsNext => Self::EsNext,
+		}
+	}
+}
+
+/// Rewrites the syntax newer than `target` with the swc compat passes:
+///
+/// - es2022: class fields, private members and static blocks.
+/// - es2021: logical assignments, numeric separators are left to the codegen.
+/// - es2020: optional chaining and nullish coalescing.
+/// - es2019: optional catch bindings.
+/// - es2018: object rest and spread.
+/// - es2017: async functions, as generators.
+/// - es2016: the exponentiation operator.
+/// - es2015: classes, arrow functions, destructuring, block scoping, generators and the rest of
+///   the ES5 lowering.
+///
+/// The helpers are inlined in the module. Needs the marks given to the resolver, so it must run
+/// before `hygiene`.
+pub fn downlevel(
+	module: ast::Module,
+	target: EsVersion,
+	unresolved_mark: Mark,
+	comments: &SingleThreadedComments,
+) -> ast::Module {
+	if target >= EsVersion::Es2022 {
+		return module;
+	}
+	HELPERS.set(&Helpers::new(false), || {
+		let mut module = module.fold_with(&mut es2022::es2022(Some(comments), Default::default()));
+		if target < EsVersion::Es2021 {
+			module = module.fold_with(&mut es2021::es2021());
+		}
+		if target < EsVersion::Es2020 {
+			module = module.fold_with(&mut es2020::es2020(Default::default()));
+		}
+		if target < EsVersion::Es2019 {
+			module = module.fold_with(&mut es2019::es2019());
+		}
+		if target < EsVersion::Es2018 {
+			module = module.fold_with(&mut es2018::es2018(Default::default()));
+		}
+		if target < EsVersion::Es2017 {
+			module = module.fold_with(&mut es2017::es2017(
+				Default::default(),
+				Some(comments),
+				unresolved_mark,
+			));
+		}
+		if target < EsVersion::Es2016 {
+			module = module.fold_with(&mut es2016::es2016());
+		}
+		if target < EsVersion::Es2015 {
+			module = module.fold_with(&mut es2015::es2015(
+				unresolved_mark,
+				Some(comments),
+				Default::default(),
+			));
+		}
+		module.fold_with(&mut inject_helpers(unresolved_mark))
+	})
+}
@@ -1,7 +1,10 @@
+use crate::errors::TransformError;
+use crate::parse::HookAnalysis;
 use crate::transform::HookData;
 use crate::transform::HookKind;
+use glob::{MatchOptions, Pattern};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use swc_atoms::JsWord;
 
 use lazy_static::lazy_static;
@@ -20,8 +23,25 @@ pub enum EntryStrategy {
 	Hook,
 	Component,
 	Smart,
+	Sized,
 }
 
+/// Options of the `Sized` strategy, sizes are in bytes of emitted code.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SizedChunks {
+	/// Entries smaller than this are merged with other small entries.
+	pub min_size: Option<usize>,
+	/// Hooks are not added to an entry beyond this size, larger hooks get a file of their own.
+	pub max_size: Option<usize>,
+	/// How often each hook is used, keyed by hook hash. Frequently used hooks are packed
+	/// together, so rarely used code is not loaded with them.
+	pub usage: Option<HashMap<String, f64>>,
+}
+
+const DEFAULT_MIN_CHUNK_SIZE: usize = 10_000;
+const DEFAULT_MAX_CHUNK_SIZE: usize = 50_000;
+
 pub trait EntryPolicy: Send + Sync {
 	fn get_entry_for_sym(
 		&self,
@@ -174,15 +194,213 @@ impl EntryPolicy for SmartStrategy {
 	}
 }
 
+/// Decides the entry of the hooks it matches, before the entry strategy does. Every condition
+/// that is set must match.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryRule {
+	/// Names of the marker functions creating the hook, like `component$` or `onClick$`.
+	pub ctx_name: Option<Vec<JsWord>>,
+	pub ctx_kind: Option<HookKind>,
+	/// Glob matched against the file the hook comes from, relative to the source directory.
+	pub origin: Option<String>,
+	/// Whether the hook captures variables from its scope.
+	pub captures: Option<bool>,
+	/// Entry of the matching hooks, when missing each of them gets a file of its own.
+	pub entry: Option<JsWord>,
+}
+
+impl EntryRule {
+	fn matches(&self, origin: Option<&Pattern>, hook_data: &HookData) -> bool {
+		const OPTIONS: MatchOptions = MatchOptions {
+			case_sensitive: true,
+			require_literal_separator: true,
+			require_literal_leading_dot: false,
+		};
+		self.ctx_name
+			.as_ref()
+			.map_or(true, |names| names.contains(&hook_data.ctx_name))
+			&& self
+				.ctx_kind
+				.map_or(true, |kind| kind == hook_data.ctx_kind)
+			&& origin.map_or(true, |origin| {
+				origin.matches_with(&hook_data.origin, OPTIONS)
+			}) && self.captures.map_or(true, |captures| {
+			captures != hook_data.scoped_idents.is_empty()
+		})
+	}
+}
+
+/// Entry rules with their origin globs parsed, see `RulesPolicy`.
+#[derive(Debug, Default)]
+pub struct EntryRules {
+	rules: Vec<(EntryRule, Option<Pattern>)>,
+}
+
+impl EntryRules {
+	pub fn new(rules: &[EntryRule]) -> Result<Self, TransformError> {
+		let rules = rules
+			.iter()
+			.map(|rule| {
+				let origin = rule
+					.origin
+					.as_deref()
+					.map(|glob| {
+						Pattern::new(glob.strip_prefix("./").unwrap_or(glob)).map_err(|err| {
+							TransformError::invalid_option(
+								"entryRules",
+								format!("{}: {}", glob, err),
+							)
+						})
+					})
+					.transpose()?;
+				Ok((rule.clone(), origin))
+			})
+			.collect::<Result<_, TransformError>>()?;
+		Ok(Self { rules })
+	}
+
+	pub const fn with_fallback<'a>(&'a self, fallback: &'a dyn EntryPolicy) -> RulesPolicy<'a> {
+		RulesPolicy {
+			rules: self,
+			fallback,
+		}
+	}
+}
+
+/// Uses the entry of the first rule matching a hook, or asks the fallback policy when none does.
+pub struct RulesPolicy<'a> {
+	rules: &'a EntryRules,
+	fallback: &'a dyn EntryPolicy,
+}
+
+impl EntryPolicy for RulesPolicy<'_> {
+	fn get_entry_for_sym(
+		&self,
+		hash: &str,
+		context: &[String],
+		hook_data: &HookData,
+	) -> Option<JsWord> {
+		self.rules
+			.rules
+			.iter()
+			.find(|(rule, origin)| rule.matches(origin.as_ref(), hook_data))
+			.map_or_else(
+				|| self.fallback.get_entry_for_sym(hash, context, hook_data),
+				|(rule, _)| rule.entry.clone(),
+			)
+	}
+}
+
 pub fn parse_entry_strategy(
 	strategy: &EntryStrategy,
 	manual_chunks: Option<HashMap<String, JsWord>>,
 ) -> Box<dyn EntryPolicy> {
 	match strategy {
 		EntryStrategy::Inline | EntryStrategy::Hoist => Box::<InlineStrategy>::default(),
-		EntryStrategy::Hook => Box::new(PerHookStrategy::new(manual_chunks)),
+		// Hooks are measured in their own files first, see `pack_hooks`
+		EntryStrategy::Hook | EntryStrategy::Sized => Box::new(PerHookStrategy::new(manual_chunks)),
 		EntryStrategy::Single => Box::new(SingleStrategy::new(manual_chunks)),
 		EntryStrategy::Component => Box::new(PerComponentStrategy::new(manual_chunks)),
 		EntryStrategy::Smart => Box::new(SmartStrategy::new(manual_chunks)),
 	}
 }
+
+struct Chunk<'a> {
+	hooks: Vec<&'a HookAnalysis>,
+	size: usize,
+	usage: f64,
+}
+
+/// Packs hooks, with the size of their emitted module, into entries for the `Sized` strategy.
+/// Hooks of the same component tree are packed together, most used first, up to `max_size`.
+/// Entries under `min_size` are then merged with each other, starting with the most used ones.
+///
+/// Returns the entry of every packed hook keyed by its hash, like `manual_chunks`. Hooks that
+/// end up alone are left out, they are imported from their own file.
+pub fn pack_hooks<'a>(
+	hooks: &[(&'a HookAnalysis, usize)],
+	options: &SizedChunks,
+) -> HashMap<String, JsWord> {
+	let min_size = options.min_size.unwrap_or(DEFAULT_MIN_CHUNK_SIZE);
+	let max_size = options.max_size.unwrap_or(DEFAULT_MAX_CHUNK_SIZE);
+	let usage = |hook: &HookAnalysis| {
+		options
+			.usage
+			.as_ref()
+			.and_then(|usage| usage.get(hook.hash.as_ref()))
+			.copied()
+			.unwrap_or_default()
+	};
+	let by_name: HashMap<&JsWord, &'a HookAnalysis> =
+		hooks.iter().map(|(hook, _)| (&hook.name, *hook)).collect();
+	let root_of = |mut hook: &'a HookAnalysis| {
+		// Parents are always in the same file, the depth guards against malformed input
+		for _ in 0..hooks.len() {
+			match hook.parent.as_ref().and_then(|parent| by_name.get(parent)) {
+				Some(parent) => hook = parent,
+				None => break,
+			}
+		}
+		hook.name.clone()
+	};
+
+	let mut trees: BTreeMap<JsWord, Vec<(&'a HookAnalysis, usize)>> = BTreeMap::new();
+	for (hook, size) in hooks {
+		trees.entry(root_of(hook)).or_default().push((hook, *size));
+	}
+
+	let mut chunks: Vec<Chunk<'a>> = vec![];
+	for mut tree in trees.into_values() {
+		tree.sort_by(|(a, _), (b, _)| {
+			usage(b)
+				.total_cmp(&usage(a))
+				.then_with(|| a.name.cmp(&b.name))
+		});
+		let mut current: Option<Chunk> = None;
+		for (hook, size) in tree {
+			match &mut current {
+				Some(chunk) if chunk.size + size <= max_size => {
+					chunk.hooks.push(hook);
+					chunk.size += size;
+				}
+				_ => {
+					chunks.extend(current.replace(Chunk {
+						hooks: vec![hook],
+						size,
+						usage: usage(hook),
+					}));
+				}
+			}
+		}
+		chunks.extend(current);
+	}
+
+	let (mut small, mut packed): (Vec<Chunk>, Vec<Chunk>) =
+		chunks.into_iter().partition(|chunk| chunk.size < min_size);
+	small.sort_by(|a, b| {
+		b.usage
+			.total_cmp(&a.usage)
+			.then_with(|| a.hooks[0].name.cmp(&b.hooks[0].name))
+	});
+	let mut current: Option<Chunk> = None;
+	for chunk in small {
+		match &mut current {
+			Some(merged) if merged.size + chunk.size <= max_size => {
+				merged.hooks.extend(chunk.hooks);
+				merged.size += chunk.size;
+			}
+			_ => packed.extend(current.replace(chunk)),
+		}
+	}
+	packed.extend(current);
+
+	let mut entries = HashMap::new();
+	for chunk in packed.into_iter().filter(|chunk| chunk.hooks.len() > 1) {
+		let entry = JsWord::from(["entry_", &chunk.hooks[0].hash].concat());
+		for hook in chunk.hooks {
+			entries.insert(hook.hash.to_string(), entry.clone());
+		}
+	}
+	entries
+}
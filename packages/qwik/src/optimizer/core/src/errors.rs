@@ -1,12 +1,146 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
 use swc_common::errors::DiagnosticId;
 
 pub enum Error {
 	FunctionReference = 2,
 	CanNotCapture,
 	MissingQrlImplementation = 5,
+	CaptureMutableObject,
+	ConditionalTask,
+	CaptureNonSerializable,
+	ServerOnlyImport,
+	ServerModuleReexport,
 }
 
 pub fn get_diagnostic_id(err: Error) -> DiagnosticId {
 	let id = err as u32;
 	DiagnosticId::Error(format!("C{:02}", id))
 }
+
+/// Errors that abort a transform. Problems in the transformed source code are not errors,
+/// they are reported as diagnostics in the `TransformOutput`.
+#[derive(Debug)]
+pub enum TransformError {
+	/// Reading or writing a file failed.
+	Io {
+		path: PathBuf,
+		source: std::io::Error,
+	},
+	/// A module path can not be resolved, relative to the source directory.
+	InvalidPath { path: PathBuf, reason: String },
+	/// An option has a value that can not be used.
+	InvalidOption { option: String, reason: String },
+	/// Generating the code of a module failed.
+	Emit { path: PathBuf, reason: String },
+	/// The source map given with a module can not be parsed.
+	InvalidSourceMap { path: PathBuf, reason: String },
+	/// The consumer of a streaming transform failed, the remaining files are not transformed.
+	Aborted { reason: String },
+}
+
+impl TransformError {
+	/// Stable code that identifies the kind of error for the bindings.
+	pub const fn code(&self) -> &'static str {
+		match self {
+			Self::Io { .. } => "ERR_IO",
+			Self::InvalidPath { .. } => "ERR_INVALID_PATH",
+			Self::InvalidOption { .. } => "ERR_INVALID_OPTION",
+			Self::Emit { .. } => "ERR_EMIT",
+			Self::InvalidSourceMap { .. } => "ERR_INVALID_SOURCE_MAP",
+			Self::Aborted { .. } => "ERR_ABORTED",
+		}
+	}
+
+	/// The file or directory the error relates to, if any.
+	pub fn path(&self) -> Option<&Path> {
+		match self {
+			Self::Io { path, .. }
+			| Self::InvalidPath { path, .. }
+			| Self::Emit { path, .. }
+			| Self::InvalidSourceMap { path, .. } => Some(path),
+			Self::InvalidOption { .. } | Self::Aborted { .. } => None,
+		}
+	}
+
+	#[cfg(feature = "fs")]
+	pub(crate) fn io<P: Into<PathBuf>>(path: P, source: std::io::Error) -> Self {
+		Self::Io {
+			path: path.into(),
+			source,
+		}
+	}
+
+	pub(crate) fn invalid_path<P: Into<PathBuf>, R: fmt::Display>(path: P, reason: R) -> Self {
+		Self::InvalidPath {
+			path: path.into(),
+			reason: reason.to_string(),
+		}
+	}
+
+	pub(crate) fn invalid_option<R: fmt::Display>(option: &str, reason: R) -> Self {
+		Self::InvalidOption {
+			option: option.into(),
+			reason: reason.to_string(),
+		}
+	}
+
+	pub(crate) fn invalid_source_map<P: Into<PathBuf>, R: fmt::Display>(
+		path: P,
+		reason: R,
+	) -> Self {
+		Self::InvalidSourceMap {
+			path: path.into(),
+			reason: reason.to_string(),
+		}
+	}
+
+	/// Returned by the `on_output` callback of a streaming transform to stop it.
+	pub fn aborted<R: fmt::Display>(reason: R) -> Self {
+		Self::Aborted {
+			reason: reason.to_string(),
+		}
+	}
+
+	pub(crate) fn emit<P: Into<PathBuf>, R: fmt::Display>(path: P, reason: R) -> Self {
+		Self::Emit {
+			path: path.into(),
+			reason: reason.to_string(),
+		}
+	}
+}
+
+impl fmt::Display for TransformError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Io { path, source } => write!(f, "{}: {}", path.to_string_lossy(), source),
+			Self::InvalidPath { path, reason } => {
+				write!(f, "Invalid path {}: {}", path.to_string_lossy(), reason)
+			}
+			Self::InvalidOption { option, reason } => {
+				write!(f, "Invalid option {}: {}", option, reason)
+			}
+			Self::Emit { path, reason } => {
+				write!(f, "Emitting {}: {}", path.to_string_lossy(), reason)
+			}
+			Self::InvalidSourceMap { path, reason } => {
+				write!(
+					f,
+					"Invalid source map of {}: {}",
+					path.to_string_lossy(),
+					reason
+				)
+			}
+			Self::Aborted { reason } => write!(f, "Transform aborted: {}", reason),
+		}
+	}
+}
+
+impl std::error::Error for TransformError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Self::Io { source, .. } => Some(source),
+			_ => None,
+		}
+	}
+}
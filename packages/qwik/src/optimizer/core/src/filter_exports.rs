@@ -1,15 +1,25 @@
-use swc_atoms::JsWord;
-use swc_common::DUMMY_SP;
+use std::collections::{HashMap, HashSet};
+
+use crate::collector::Id;
+use crate::errors;
+use swc_atoms::{js_word, JsWord};
+use swc_common::errors::HANDLER;
+use swc_common::{Span, Spanned, DUMMY_SP};
 use swc_ecmascript::ast;
-use swc_ecmascript::visit::VisitMut;
+use swc_ecmascript::utils::{find_pat_ids, private_ident};
+use swc_ecmascript::visit::{Visit, VisitMut, VisitWith};
 
 pub struct StripExportsVisitor<'a> {
 	pub filter_symbols: &'a [JsWord],
+	pub did_strip: bool,
 }
 
 impl<'a> StripExportsVisitor<'a> {
 	pub const fn new(filter_symbols: &'a [JsWord]) -> Self {
-		Self { filter_symbols }
+		Self {
+			filter_symbols,
+			did_strip: false,
+		}
 	}
 }
 
@@ -27,6 +37,7 @@ impl<'a> VisitMut for StripExportsVisitor<'a> {
 							{
 								if self.filter_symbols.contains(&ident.id.sym) {
 									*item = empty_module_item(ident.id.clone());
+									self.did_strip = true;
 								}
 							}
 						}
@@ -34,6 +45,7 @@ impl<'a> VisitMut for StripExportsVisitor<'a> {
 					ast::Decl::Fn(fn_decl) => {
 						if self.filter_symbols.contains(&fn_decl.ident.sym) {
 							*item = empty_module_item(fn_decl.ident.clone());
+							self.did_strip = true;
 						}
 					}
 					_ => {}
@@ -43,40 +55,482 @@ impl<'a> VisitMut for StripExportsVisitor<'a> {
 	}
 }
 
+const USE_SERVER: &str = "use server";
+
+/// Extensions of the server only modules, imports can leave them out.
+const SERVER_ONLY_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "mjs"];
+
+/// Whether a module is server only by its name, like `db.server.ts` or `./db.server`. The SSR
+/// entry `entry.server.tsx` is not, it renders the app on the server.
+pub fn is_server_only_path(path: &str) -> bool {
+	let file_name = path.rsplit('/').next().unwrap_or(path);
+	let stem = match file_name.rsplit_once('.') {
+		Some((stem, ext)) if SERVER_ONLY_EXTENSIONS.contains(&ext) => stem,
+		_ => file_name,
+	};
+	matches!(stem.strip_suffix(".server"), Some(name) if !name.is_empty() && name != "entry")
+}
+
+/// Replaces the server only code with throwing stubs, for client builds. Every export of a
+/// server only module is replaced, the module is named like `*.server.ts` or starts with a
+/// `"use server"` directive. Otherwise only the exported functions whose body starts with the
+/// directive are replaced. Returns whether anything was replaced.
+pub fn strip_server_code(module: &mut ast::Module, file_name: &str) -> bool {
+	let leading_stmts = module.body.iter().map_while(|item| match item {
+		ast::ModuleItem::Stmt(stmt) => Some(stmt),
+		ast::ModuleItem::ModuleDecl(_) => None,
+	});
+	if is_server_only_path(file_name) || has_use_server(leading_stmts) {
+		module.body = server_module_stubs(&module.body);
+		return true;
+	}
+	let mut did_strip = false;
+	for item in &mut module.body {
+		if let ast::ModuleItem::ModuleDecl(ast::ModuleDecl::ExportDecl(decl)) = item {
+			match &decl.decl {
+				ast::Decl::Fn(fn_decl) if is_server_function(&fn_decl.function) => {
+					*item = empty_module_item(fn_decl.ident.clone());
+					did_strip = true;
+				}
+				ast::Decl::Var(var) => {
+					if let [ast::VarDeclarator {
+						name: ast::Pat::Ident(ident),
+						init: Some(init),
+						..
+					}] = var.decls.as_slice()
+					{
+						let is_server = match &**init {
+							ast::Expr::Fn(fn_expr) => is_server_function(&fn_expr.function),
+							ast::Expr::Arrow(ast::ArrowExpr {
+								body: box ast::BlockStmtOrExpr::BlockStmt(body),
+								..
+							}) => has_use_server(&body.stmts),
+							_ => false,
+						};
+						if is_server {
+							*item = empty_module_item(ident.id.clone());
+							did_strip = true;
+						}
+					}
+				}
+				_ => {}
+			}
+		}
+	}
+	did_strip
+}
+
+fn is_server_function(function: &ast::Function) -> bool {
+	function
+		.body
+		.as_ref()
+		.map_or(false, |body| has_use_server(&body.stmts))
+}
+
+/// Whether the directives at the start of `stmts` include `"use server"`.
+fn has_use_server<'a>(stmts: impl IntoIterator<Item = &'a ast::Stmt>) -> bool {
+	stmts
+		.into_iter()
+		.map_while(|stmt| match stmt {
+			ast::Stmt::Expr(ast::ExprStmt {
+				expr: box ast::Expr::Lit(ast::Lit::Str(directive)),
+				..
+			}) => Some(directive),
+			_ => None,
+		})
+		.any(|directive| &*directive.value == USE_SERVER)
+}
+
+/// A stub for every name exported by `body`, the rest of the module is dropped. The names
+/// re-exported by `export * from` are not known, they are reported as an error.
+fn server_module_stubs(body: &[ast::ModuleItem]) -> Vec<ast::ModuleItem> {
+	let mut names: Vec<ast::ModuleExportName> = vec![];
+	for item in body {
+		match item {
+			ast::ModuleItem::ModuleDecl(ast::ModuleDecl::ExportDecl(decl)) => match &decl.decl {
+				ast::Decl::Fn(fn_decl) => names.push(export_ident(&fn_decl.ident)),
+				ast::Decl::Class(class_decl) => names.push(export_ident(&class_decl.ident)),
+				ast::Decl::TsEnum(enum_decl) if !enum_decl.declare => {
+					names.push(export_ident(&enum_decl.id));
+				}
+				ast::Decl::Var(var) => names.extend(var.decls.iter().flat_map(|decl| {
+					find_pat_ids::<_, ast::Ident>(&decl.name)
+						.into_iter()
+						.map(|ident| export_ident(&ident))
+				})),
+				_ => {}
+			},
+			ast::ModuleItem::ModuleDecl(ast::ModuleDecl::ExportNamed(named))
+				if !named.type_only =>
+			{
+				names.extend(
+					named
+						.specifiers
+						.iter()
+						.filter_map(|specifier| match specifier {
+							ast::ExportSpecifier::Named(ast::ExportNamedSpecifier {
+								is_type_only: false,
+								orig,
+								exported,
+								..
+							}) => Some(exported.as_ref().unwrap_or(orig).clone()),
+							ast::ExportSpecifier::Namespace(namespace) => {
+								Some(namespace.name.clone())
+							}
+							_ => None,
+						}),
+				);
+			}
+			ast::ModuleItem::ModuleDecl(
+				ast::ModuleDecl::ExportDefaultDecl(_) | ast::ModuleDecl::ExportDefaultExpr(_),
+			) => names.push(ast::ModuleExportName::Ident(ast::Ident::new(
+				js_word!("default"),
+				DUMMY_SP,
+			))),
+			ast::ModuleItem::ModuleDecl(ast::ModuleDecl::ExportAll(export_all))
+				if !export_all.type_only =>
+			{
+				HANDLER.with(|handler| {
+					handler
+						.struct_span_err_with_code(
+							export_all.span,
+							&format!("\"export * from '{}'\" can not be removed from a server only module, export the names one by one", export_all.src.value),
+							errors::get_diagnostic_id(errors::Error::ServerModuleReexport),
+						)
+						.emit();
+				});
+			}
+			_ => {}
+		}
+	}
+	let mut stubs = vec![];
+	for name in names {
+		match name {
+			ast::ModuleExportName::Ident(ident) if ident.sym == js_word!("default") => {
+				stubs.push(ast::ModuleItem::ModuleDecl(
+					ast::ModuleDecl::ExportDefaultExpr(ast::ExportDefaultExpr {
+						span: DUMMY_SP,
+						expr: Box::new(removed_symbol_stub()),
+					}),
+				));
+			}
+			ast::ModuleExportName::Ident(ident) => stubs.push(empty_module_item(ident)),
+			ast::ModuleExportName::Str(str) => {
+				let local = private_ident!("_removed");
+				stubs.push(ast::ModuleItem::Stmt(ast::Stmt::Decl(ast::Decl::Var(
+					Box::new(ast::VarDecl {
+						span: DUMMY_SP,
+						kind: ast::VarDeclKind::Const,
+						declare: false,
+						decls: vec![ast::VarDeclarator {
+							definite: false,
+							span: DUMMY_SP,
+							name: ast::Pat::Ident(ast::BindingIdent::from(local.clone())),
+							init: Some(Box::new(removed_symbol_stub())),
+						}],
+					}),
+				))));
+				stubs.push(ast::ModuleItem::ModuleDecl(ast::ModuleDecl::ExportNamed(
+					ast::NamedExport {
+						span: DUMMY_SP,
+						specifiers: vec![ast::ExportSpecifier::Named(ast::ExportNamedSpecifier {
+							span: DUMMY_SP,
+							orig: ast::ModuleExportName::Ident(local),
+							exported: Some(ast::ModuleExportName::Str(ast::Str::from(str.value))),
+							is_type_only: false,
+						})],
+						src: None,
+						type_only: false,
+						asserts: None,
+					},
+				)));
+			}
+		}
+	}
+	stubs
+}
+
+fn export_ident(ident: &ast::Ident) -> ast::ModuleExportName {
+	ast::ModuleExportName::Ident(ast::Ident::new(ident.sym.clone(), DUMMY_SP))
+}
+
+/// The imports of server only modules, by their local bindings.
+pub fn server_only_imports(module: &ast::Module) -> HashMap<Id, (JsWord, Span)> {
+	let mut imports = HashMap::new();
+	for item in &module.body {
+		if let ast::ModuleItem::ModuleDecl(ast::ModuleDecl::Import(import)) = item {
+			if import.type_only || !is_server_only_path(&import.src.value) {
+				continue;
+			}
+			for specifier in &import.specifiers {
+				let local = match specifier {
+					ast::ImportSpecifier::Named(ast::ImportNamedSpecifier {
+						is_type_only: true,
+						..
+					}) => continue,
+					ast::ImportSpecifier::Named(specifier) => &specifier.local,
+					ast::ImportSpecifier::Default(specifier) => &specifier.local,
+					ast::ImportSpecifier::Namespace(specifier) => &specifier.local,
+				};
+				imports.insert(
+					(local.sym.clone(), local.span.ctxt()),
+					(import.src.value.clone(), import.span()),
+				);
+			}
+		}
+	}
+	imports
+}
+
+/// Adds the server only imports `module` still uses to `used`, the module is going to the
+/// client so calling them throws.
+pub fn collect_used_server_imports(
+	module: &ast::Module,
+	server_imports: &HashMap<Id, (JsWord, Span)>,
+	used: &mut HashSet<Id>,
+) {
+	if server_imports.is_empty() {
+		return;
+	}
+	let mut finder = UsedIdents {
+		server_imports,
+		used,
+	};
+	for item in &module.body {
+		if !matches!(
+			item,
+			ast::ModuleItem::ModuleDecl(ast::ModuleDecl::Import(_))
+		) {
+			item.visit_with(&mut finder);
+		}
+	}
+}
+
+struct UsedIdents<'a> {
+	server_imports: &'a HashMap<Id, (JsWord, Span)>,
+	used: &'a mut HashSet<Id>,
+}
+
+impl<'a> Visit for UsedIdents<'a> {
+	fn visit_ident(&mut self, node: &ast::Ident) {
+		let id = (node.sym.clone(), node.span.ctxt());
+		if self.server_imports.contains_key(&id) {
+			self.used.insert(id);
+		}
+	}
+}
+
+/// A top level binding that can be removed when nothing else uses it: an import specifier, or
+/// a declaration that is not exported.
+struct Removable {
+	item: usize,
+	/// The declarator or import specifier, `None` for a function or class.
+	part: Option<usize>,
+	names: Vec<JsWord>,
+	refs: HashSet<JsWord>,
+}
+
+/// The names of the top level bindings the module uses, collected before the exports are
+/// stripped and given to `remove_unused_declarations`.
+pub fn used_top_level_names(module: &ast::Module) -> HashSet<JsWord> {
+	let (removables, roots) = collect_removables(module);
+	reachable_names(&removables, roots)
+}
+
+/// Removes the top level declarations and imports that were used before stripping, given by
+/// `used_before`, and that nothing uses anymore. Run after the exports were replaced by stubs
+/// so their helpers and dependencies don't reach the client, while the declarations that
+/// were already unused, like `const _ = registerSW()`, are kept. Imports without specifiers
+/// are kept, they are there for their side effects.
+///
+/// Runs before the resolver, so the bindings are matched by name: a shadowed name keeps the
+/// top level one alive.
+pub fn remove_unused_declarations(module: &mut ast::Module, used_before: &HashSet<JsWord>) {
+	let (removables, roots) = collect_removables(module);
+	let used = reachable_names(&removables, roots);
+
+	let mut unused: HashMap<usize, HashSet<Option<usize>>> = HashMap::new();
+	for removable in removables {
+		if !removable.names.iter().any(|name| used.contains(name))
+			&& removable
+				.names
+				.iter()
+				.any(|name| used_before.contains(name))
+		{
+			unused
+				.entry(removable.item)
+				.or_default()
+				.insert(removable.part);
+		}
+	}
+	if unused.is_empty() {
+		return;
+	}
+	let body = std::mem::take(&mut module.body);
+	module.body = body
+		.into_iter()
+		.enumerate()
+		.filter_map(|(index, mut item)| {
+			let Some(parts) = unused.get(&index) else {
+				return Some(item);
+			};
+			match &mut item {
+				ast::ModuleItem::ModuleDecl(ast::ModuleDecl::Import(import)) => {
+					import.specifiers = remove_parts(std::mem::take(&mut import.specifiers), parts);
+					(!import.specifiers.is_empty()).then_some(item)
+				}
+				ast::ModuleItem::Stmt(ast::Stmt::Decl(ast::Decl::Var(var))) => {
+					var.decls = remove_parts(std::mem::take(&mut var.decls), parts);
+					(!var.decls.is_empty()).then_some(item)
+				}
+				_ => None,
+			}
+		})
+		.collect();
+}
+
+/// The top level bindings that can be removed, and the names referenced by the other items.
+fn collect_removables(module: &ast::Module) -> (Vec<Removable>, HashSet<JsWord>) {
+	let mut removables = vec![];
+	let mut roots = HashSet::new();
+	for (index, item) in module.body.iter().enumerate() {
+		match item {
+			ast::ModuleItem::ModuleDecl(ast::ModuleDecl::Import(import)) => {
+				for (part, specifier) in import.specifiers.iter().enumerate() {
+					let local = match specifier {
+						ast::ImportSpecifier::Named(specifier) => &specifier.local,
+						ast::ImportSpecifier::Default(specifier) => &specifier.local,
+						ast::ImportSpecifier::Namespace(specifier) => &specifier.local,
+					};
+					removables.push(Removable {
+						item: index,
+						part: Some(part),
+						names: vec![local.sym.clone()],
+						refs: HashSet::new(),
+					});
+				}
+			}
+			ast::ModuleItem::Stmt(ast::Stmt::Decl(ast::Decl::Fn(fn_decl))) => {
+				removables.push(Removable {
+					item: index,
+					part: None,
+					names: vec![fn_decl.ident.sym.clone()],
+					refs: referenced_names(&*fn_decl.function),
+				});
+			}
+			ast::ModuleItem::Stmt(ast::Stmt::Decl(ast::Decl::Class(class_decl))) => {
+				removables.push(Removable {
+					item: index,
+					part: None,
+					names: vec![class_decl.ident.sym.clone()],
+					refs: referenced_names(&*class_decl.class),
+				});
+			}
+			ast::ModuleItem::Stmt(ast::Stmt::Decl(ast::Decl::Var(var))) => {
+				for (part, decl) in var.decls.iter().enumerate() {
+					removables.push(Removable {
+						item: index,
+						part: Some(part),
+						names: find_pat_ids::<_, ast::Ident>(&decl.name)
+							.into_iter()
+							.map(|ident| ident.sym)
+							.collect(),
+						refs: referenced_names(decl),
+					});
+				}
+			}
+			_ => roots.extend(referenced_names(item)),
+		}
+	}
+
+	(removables, roots)
+}
+
+/// Everything reachable from the roots is used.
+fn reachable_names(removables: &[Removable], roots: HashSet<JsWord>) -> HashSet<JsWord> {
+	let mut used = HashSet::new();
+	let mut pending: Vec<JsWord> = roots.into_iter().collect();
+	while let Some(name) = pending.pop() {
+		if !used.insert(name.clone()) {
+			continue;
+		}
+		for removable in removables {
+			if removable.names.contains(&name) {
+				pending.extend(removable.refs.iter().cloned());
+			}
+		}
+	}
+	used
+}
+
+fn remove_parts<T>(parts: Vec<T>, unused: &HashSet<Option<usize>>) -> Vec<T> {
+	parts
+		.into_iter()
+		.enumerate()
+		.filter(|(index, _)| !unused.contains(&Some(*index)))
+		.map(|(_, part)| part)
+		.collect()
+}
+
+fn referenced_names<N: VisitWith<NameCollector>>(node: &N) -> HashSet<JsWord> {
+	let mut collector = NameCollector {
+		names: HashSet::new(),
+	};
+	node.visit_with(&mut collector);
+	collector.names
+}
+
+struct NameCollector {
+	names: HashSet<JsWord>,
+}
+
+impl Visit for NameCollector {
+	fn visit_ident(&mut self, node: &ast::Ident) {
+		self.names.insert(node.sym.clone());
+	}
+}
+
+/// `() => { throw "Symbol removed..." }`
+fn removed_symbol_stub() -> ast::Expr {
+	ast::Expr::Arrow(ast::ArrowExpr {
+		is_async: false,
+		is_generator: false,
+		params: vec![],
+		return_type: None,
+		span: DUMMY_SP,
+		type_params: None,
+		body: Box::new(ast::BlockStmtOrExpr::BlockStmt(ast::BlockStmt {
+			span: DUMMY_SP,
+			stmts: vec![ast::Stmt::Throw(ast::ThrowStmt {
+				span: DUMMY_SP,
+				arg: Box::new(ast::Expr::Lit(ast::Lit::Str(ast::Str {
+					span: DUMMY_SP,
+					value: JsWord::from(
+						"Symbol removed by Qwik Optimizer, it can not be called from current platform",
+					),
+					raw: None,
+				}))),
+			})],
+		})),
+	})
+}
+
 fn empty_module_item(ident: ast::Ident) -> ast::ModuleItem {
 	ast::ModuleItem::ModuleDecl(ast::ModuleDecl::ExportDecl(ast::ExportDecl {
-        span: DUMMY_SP,
-        decl: ast::Decl::Var(Box::new(ast::VarDecl {
-            span: DUMMY_SP,
-            kind: ast::VarDeclKind::Const,
-            declare: false,
-            decls: vec![ast::VarDeclarator {
-                definite: true,
-                span: DUMMY_SP,
-                name: ast::Pat::Ident(ast::BindingIdent {
-                    id: ident,
-                    type_ann: None,
-                }),
-                init: Some(Box::new(ast::Expr::Arrow(ast::ArrowExpr {
-                    is_async: false,
-                    is_generator: false,
-                    params: vec![],
-                    return_type: None,
-                    span: DUMMY_SP,
-                    type_params: None,
-                    body: Box::new(ast::BlockStmtOrExpr::BlockStmt(ast::BlockStmt {
-                        span: DUMMY_SP,
-                        stmts: vec![ast::Stmt::Throw(ast::ThrowStmt {
-                            span: DUMMY_SP,
-                            arg: Box::new(ast::Expr::Lit(ast::Lit::Str(ast::Str {
-                                span: DUMMY_SP,
-                                value: JsWord::from("Symbol removed by Qwik Optimizer, it can not be called from current platform"),
-                                raw: None,
-                            }))),
-                        })],
-                    })),
-                }))),
-            }],
-        })),
-    }))
+		span: DUMMY_SP,
+		decl: ast::Decl::Var(Box::new(ast::VarDecl {
+			span: DUMMY_SP,
+			kind: ast::VarDeclKind::Const,
+			declare: false,
+			decls: vec![ast::VarDeclarator {
+				definite: true,
+				span: DUMMY_SP,
+				name: ast::Pat::Ident(ast::BindingIdent {
+					id: ident,
+					type_ann: None,
+				}),
+				init: Some(Box::new(removed_symbol_stub())),
+			}],
+		})),
+	}))
 }
@@ -0,0 +1,30 @@
+use siphasher::sip::SipHasher13;
+use std::hash::Hasher;
+
+/// Version of the hashing scheme behind symbol names and cache keys. Bump it whenever
+/// `StableHasher` produces a different output, since that renames every symbol.
+pub const HASH_VERSION: u32 = 1;
+
+/// SipHash-1-3 with zero keys, the algorithm `DefaultHasher::new()` uses today. Unlike
+/// `DefaultHasher`, its output is not allowed to change between Rust releases, so symbol
+/// names stay the same across deploys.
+///
+/// Only hash bytes with it, integers are written in the platform's byte order.
+#[derive(Debug, Default, Clone)]
+pub struct StableHasher(SipHasher13);
+
+impl StableHasher {
+	pub fn new() -> Self {
+		Self::default()
+	}
+}
+
+impl Hasher for StableHasher {
+	fn write(&mut self, bytes: &[u8]) {
+		self.0.write(bytes);
+	}
+
+	fn finish(&self) -> u64 {
+		self.0.finish()
+	}
+}
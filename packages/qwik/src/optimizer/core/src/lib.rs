@@ -10,19 +10,25 @@
 mod test;
 
 mod add_side_effect;
+mod cache;
 mod clean_side_effects;
 mod code_move;
 mod collector;
 mod const_replace;
+mod downlevel;
 mod entry_strategy;
 mod errors;
 mod filter_exports;
 mod has_branches;
+mod hash;
 mod inlined_fn;
 mod is_immutable;
+mod lint;
+mod minify;
 mod package_json;
 mod parse;
 mod props_destructuring;
+mod trace;
 mod transform;
 mod utils;
 mod words;
@@ -30,26 +36,38 @@ mod words;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
-#[cfg(feature = "parallel")]
-use anyhow::Context;
-use words::BUILDER_IO_QWIK;
+use words::{BUILDER_IO_QWIK, FRAGMENT, H};
 
+#[cfg(feature = "fs")]
+use crate::package_json::FileFilter;
+#[cfg(feature = "fs")]
+pub use crate::package_json::Globs;
 #[cfg(feature = "fs")]
 use std::fs;
 
-use anyhow::Error;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 use std::str;
 use swc_atoms::JsWord;
+use swc_ecmascript::ast::EsVersion;
 
+pub use crate::cache::{CacheStats, TransformCache};
 use crate::code_move::generate_entries;
-use crate::entry_strategy::parse_entry_strategy;
-pub use crate::entry_strategy::EntryStrategy;
+use crate::const_replace::Defines;
+pub use crate::downlevel::EsTarget;
+use crate::entry_strategy::{pack_hooks, parse_entry_strategy, EntryRules, PerHookStrategy};
+pub use crate::entry_strategy::{EntryPolicy, EntryRule, EntryStrategy, SizedChunks};
+pub use crate::errors::TransformError;
+pub use crate::hash::HASH_VERSION;
 pub use crate::parse::EmitMode;
-use crate::parse::{transform_code, TransformCodeOptions};
-pub use crate::parse::{ErrorBuffer, HookAnalysis, MinifyMode, TransformModule, TransformOutput};
+use crate::parse::{analyze_code, transform_code, SourceMapOptions, TransformCodeOptions};
+pub use crate::parse::{
+	AnalyzeOutput, AnalyzedHook, CapturedIdent, ErrorBuffer, HookAnalysis, MinifyMode,
+	ModuleAnalysis, TransformModule, TransformOutput,
+};
+pub use crate::trace::{SymbolLoad, SymbolTraces, TraceReport};
+pub use crate::transform::{HookData, HookKind};
 
 #[cfg(feature = "fs")]
 #[derive(Serialize, Debug, Deserialize)]
@@ -58,11 +76,38 @@ pub struct TransformFsOptions {
 	pub src_dir: String,
 	pub root_dir: Option<String>,
 	pub vendor_roots: Vec<String>,
-	pub glob: Option<String>,
+	/// Include and exclude (prefixed with `!`) glob patterns, relative to the source directory
+	/// or vendor root, applied to the discovered files.
+	pub glob: Option<Globs>,
 	pub minify: MinifyMode,
 	pub entry_strategy: EntryStrategy,
 	pub manual_chunks: Option<HashMap<String, JsWord>>,
+	pub sized_chunks: Option<SizedChunks>,
+	/// Recorded symbol loads, symbols loaded together are grouped into the same entry.
+	pub symbol_traces: Option<SymbolTraces>,
+	/// Rules deciding the entry of the hooks they match, checked in order before the entry
+	/// strategy and its manual chunks.
+	pub entry_rules: Option<Vec<EntryRule>>,
+	/// The ECMAScript version of the emitted code, newer syntax is rewritten. Defaults to the
+	/// latest version.
+	pub es_target: Option<EsTarget>,
+	/// The module providing the automatic JSX runtime at `<source>/jsx-runtime`, defaults to
+	/// the core module. Files can override it with a `@jsxImportSource` comment.
+	pub jsx_import_source: Option<String>,
+	/// The function the classic JSX runtime calls, defaults to `h`.
+	pub jsx_pragma: Option<String>,
+	/// The fragment component of the classic JSX runtime, defaults to `Fragment`.
+	pub jsx_pragma_frag: Option<String>,
+	/// Identifiers replaced by JSON values before the hooks are extracted, the branches made dead
+	/// are removed. The keys are globals like `process.env.NODE_ENV` or imports written
+	/// `<source>:<specifier>`.
+	pub define: Option<HashMap<String, serde_json::Value>>,
 	pub source_maps: bool,
+	/// Appends the source maps to the code as `sourceMappingURL` data comments, instead of
+	/// returning them in `map`.
+	pub inline_source_maps: bool,
+	/// Includes the original source code in the `sourcesContent` of the source maps.
+	pub sources_content: bool,
 	pub transpile_ts: bool,
 	pub transpile_jsx: bool,
 	pub preserve_filenames: bool,
@@ -83,6 +128,9 @@ pub struct TransformFsOptions {
 pub struct TransformModuleInput {
 	pub path: String,
 	pub code: String,
+	/// Source map of the code, when it was generated from another source. The maps of the output
+	/// point to the sources of this map.
+	pub map: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -92,12 +140,37 @@ pub struct TransformModulesOptions {
 	pub root_dir: Option<String>,
 	pub input: Vec<TransformModuleInput>,
 	pub source_maps: bool,
+	/// Appends the source maps to the code as `sourceMappingURL` data comments, instead of
+	/// returning them in `map`.
+	pub inline_source_maps: bool,
+	/// Includes the original source code in the `sourcesContent` of the source maps.
+	pub sources_content: bool,
 	pub minify: MinifyMode,
 	pub transpile_ts: bool,
 	pub transpile_jsx: bool,
 	pub preserve_filenames: bool,
 	pub entry_strategy: EntryStrategy,
 	pub manual_chunks: Option<HashMap<String, JsWord>>,
+	pub sized_chunks: Option<SizedChunks>,
+	/// Recorded symbol loads, symbols loaded together are grouped into the same entry.
+	pub symbol_traces: Option<SymbolTraces>,
+	/// Rules deciding the entry of the hooks they match, checked in order before the entry
+	/// strategy and its manual chunks.
+	pub entry_rules: Option<Vec<EntryRule>>,
+	/// The ECMAScript version of the emitted code, newer syntax is rewritten. Defaults to the
+	/// latest version.
+	pub es_target: Option<EsTarget>,
+	/// The module providing the automatic JSX runtime at `<source>/jsx-runtime`, defaults to
+	/// the core module. Files can override it with a `@jsxImportSource` comment.
+	pub jsx_import_source: Option<String>,
+	/// The function the classic JSX runtime calls, defaults to `h`.
+	pub jsx_pragma: Option<String>,
+	/// The fragment component of the classic JSX runtime, defaults to `Fragment`.
+	pub jsx_pragma_frag: Option<String>,
+	/// Identifiers replaced by JSON values before the hooks are extracted, the branches made dead
+	/// are removed. The keys are globals like `process.env.NODE_ENV` or imports written
+	/// `<source>:<specifier>`.
+	pub define: Option<HashMap<String, serde_json::Value>>,
 	pub explicit_extensions: bool,
 	pub mode: EmitMode,
 	pub scope: Option<String>,
@@ -110,140 +183,525 @@ pub struct TransformModulesOptions {
 	pub is_server: Option<bool>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyzeModulesOptions {
+	pub src_dir: String,
+	pub root_dir: Option<String>,
+	pub input: Vec<TransformModuleInput>,
+	pub mode: EmitMode,
+	pub scope: Option<String>,
+
+	pub core_module: Option<String>,
+	pub is_server: Option<bool>,
+	/// The module providing the automatic JSX runtime, like the `jsx_import_source` option of
+	/// `transform_modules`.
+	pub jsx_import_source: Option<String>,
+	/// The function the classic JSX runtime calls, defaults to `h`.
+	pub jsx_pragma: Option<String>,
+	/// The fragment component of the classic JSX runtime, defaults to `Fragment`.
+	pub jsx_pragma_frag: Option<String>,
+	/// Identifiers replaced by JSON values before the hooks are extracted, like the `define`
+	/// option of `transform_modules`.
+	pub define: Option<HashMap<String, serde_json::Value>>,
+}
+
+#[cfg(feature = "fs")]
+pub fn transform_fs(config: TransformFsOptions) -> Result<TransformOutput, TransformError> {
+	transform_fs_cached(config, None, None, None)
+}
+
+/// Like `transform_fs`, but files that were already transformed with the same source code
+/// and options are taken from `cache` instead of being transformed again.
+#[cfg(feature = "fs")]
+pub fn transform_fs_with_cache(
+	config: TransformFsOptions,
+	cache: &TransformCache,
+) -> Result<TransformOutput, TransformError> {
+	transform_fs_cached(config, Some(cache), None, None)
+}
+
+/// Like `transform_fs`, but hooks are grouped into entries by `entry_policy` instead of the
+/// entry strategy of `config`, which still decides whether hooks are inlined.
+#[cfg(feature = "fs")]
+pub fn transform_fs_with_entry_policy(
+	config: TransformFsOptions,
+	entry_policy: &dyn EntryPolicy,
+) -> Result<TransformOutput, TransformError> {
+	transform_fs_cached(config, None, Some(entry_policy), None)
+}
+
+/// Receives the output of each file of a streaming transform, as soon as it is transformed.
+/// Returning an error stops the transform, the files not transformed yet are skipped.
+pub type OnOutput<'a> = dyn Fn(TransformOutput) -> Result<(), TransformError> + Sync + 'a;
+
+/// Like `transform_fs`, but the output of each file is passed to `on_output` as soon as it is
+/// transformed, followed by the entry modules, instead of being kept until the end. The returned
+/// output has the diagnostics of every file, its modules are left without their code.
+#[cfg(feature = "fs")]
+pub fn transform_fs_streaming(
+	config: TransformFsOptions,
+	on_output: &OnOutput,
+) -> Result<TransformOutput, TransformError> {
+	transform_fs_cached(config, None, None, Some(on_output))
+}
+
 #[cfg(feature = "fs")]
-pub fn transform_fs(config: TransformFsOptions) -> Result<TransformOutput, Error> {
+fn transform_fs_cached(
+	config: TransformFsOptions,
+	cache: Option<&TransformCache>,
+	custom_policy: Option<&dyn EntryPolicy>,
+	on_output: Option<&OnOutput>,
+) -> Result<TransformOutput, TransformError> {
+	let options_key = if cache.is_some() {
+		serde_json::to_value(&config)
+			.map_err(|err| TransformError::invalid_option("options", err))?
+			.to_string()
+	} else {
+		String::new()
+	};
 	let core_module = config
 		.core_module
 		.map_or(BUILDER_IO_QWIK.clone(), |s| s.into());
+	let es_target = config
+		.es_target
+		.map_or(EsVersion::latest(), EsVersion::from);
+	let jsx_import_source = config
+		.jsx_import_source
+		.map_or_else(|| core_module.clone(), |s| s.into());
+	let jsx_pragma = config.jsx_pragma.map_or(H.clone(), |s| s.into());
+	let jsx_pragma_frag = config
+		.jsx_pragma_frag
+		.map_or(FRAGMENT.clone(), |s| s.into());
+	let define = Defines::new(&config.define.unwrap_or_default())?;
 	let src_dir = Path::new(&config.src_dir);
 	let root_dir = config.root_dir.as_ref().map(Path::new);
 
 	let mut paths = vec![];
-	let entry_policy = &*parse_entry_strategy(&config.entry_strategy, config.manual_chunks);
-	crate::package_json::find_modules(src_dir, config.vendor_roots, &mut paths)?;
+	let filter = FileFilter::new(config.glob.as_ref().map_or(&[], Globs::as_slice))?;
+	crate::package_json::find_modules(src_dir, config.vendor_roots, &filter, &mut paths)?;
 
-	#[cfg(feature = "parallel")]
-	let iterator = paths.par_iter();
+	let entry_rules = EntryRules::new(config.entry_rules.as_deref().unwrap_or_default())?;
+	let transform_all = |entry_policy: &dyn EntryPolicy,
+	                     entries_key: &str,
+	                     on_output: Option<&OnOutput>| {
+		let entry_policy = &entry_rules.with_fallback(entry_policy);
+		#[cfg(feature = "parallel")]
+		let iterator = paths.par_iter();
 
-	#[cfg(not(feature = "parallel"))]
-	let iterator = paths.iter();
-	let mut final_output = iterator
-		.map(|path| -> Result<TransformOutput, Error> {
-			let code = fs::read_to_string(path)
-				.with_context(|| format!("Opening {}", &path.to_string_lossy()))?;
-
-			let relative_path = pathdiff::diff_paths(path, &config.src_dir).unwrap();
-			transform_code(TransformCodeOptions {
-				src_dir,
+		#[cfg(not(feature = "parallel"))]
+		let iterator = paths.iter();
+		let iterator = iterator.map(|path| -> Result<TransformOutput, TransformError> {
+			let code = fs::read_to_string(path).map_err(|err| TransformError::io(path, err))?;
+
+			let relative_path = pathdiff::diff_paths(path, &config.src_dir).ok_or_else(|| {
+				TransformError::invalid_path(
+					path,
+					"can not be made relative to the source directory",
+				)
+			})?;
+			let relative_path = relative_path
+				.to_str()
+				.ok_or_else(|| TransformError::invalid_path(path, "is not valid UTF-8"))?;
+			let transform = || {
+				transform_code(TransformCodeOptions {
+					src_dir,
+					root_dir,
+					relative_path,
+					minify: config.minify,
+					es_target,
+					code: &code,
+					input_map: None,
+					explicit_extensions: config.explicit_extensions,
+					source_maps: config.source_maps,
+					inline_source_maps: config.inline_source_maps,
+					sources_content: config.sources_content,
+					transpile_jsx: config.transpile_jsx,
+					transpile_ts: config.transpile_ts,
+					preserve_filenames: config.preserve_filenames,
+					scope: config.scope.as_ref(),
+					entry_policy,
+					mode: config.mode,
+					core_module: core_module.clone(),
+					jsx_import_source: jsx_import_source.clone(),
+					jsx_pragma: jsx_pragma.clone(),
+					jsx_pragma_frag: jsx_pragma_frag.clone(),
+					define: &define,
+					entry_strategy: config.entry_strategy,
+					reg_ctx_name: config.reg_ctx_name.as_deref(),
+					strip_exports: config.strip_exports.as_deref(),
+					strip_ctx_name: config.strip_ctx_name.as_deref(),
+					strip_event_handlers: config.strip_event_handlers,
+					is_server: config.is_server,
+				})
+			};
+			let output = match cache {
+				Some(cache) => cache.get_or_insert_with(
+					&[&options_key, entries_key].concat(),
+					relative_path,
+					&code,
+					None,
+					transform,
+				)?,
+				None => transform()?,
+			};
+			match on_output {
+				Some(on_output) => {
+					let summary = output.without_code();
+					on_output(output)?;
+					Ok(summary)
+				}
+				None => Ok(output),
+			}
+		});
+
+		// Stops at the first error, like a failing `on_output`
+		#[cfg(feature = "parallel")]
+		let final_output: Result<TransformOutput, TransformError> =
+			iterator.try_reduce(TransformOutput::new, |x, mut y| Ok(x.append(&mut y)));
+
+		#[cfg(not(feature = "parallel"))]
+		let final_output: Result<TransformOutput, TransformError> =
+			{ iterator }.try_fold(TransformOutput::new(), |x, y| Ok(x.append(&mut y?)));
+
+		final_output
+	};
+	let manual_chunks = with_trace_entries(config.manual_chunks, config.symbol_traces.as_ref());
+	let mut final_output = if let Some(entry_policy) = custom_policy {
+		transform_all(entry_policy, "", on_output)?
+	} else if matches!(config.entry_strategy, EntryStrategy::Sized) {
+		transform_sized(
+			manual_chunks,
+			config.sized_chunks.as_ref(),
+			on_output,
+			transform_all,
+		)?
+	} else {
+		transform_all(
+			&*parse_entry_strategy(&config.entry_strategy, manual_chunks),
+			"",
+			on_output,
+		)?
+	};
+
+	final_output.modules.sort_unstable_by_key(|key| key.order);
+	// Hooks are only grouped into entries by the `Hook` strategy when symbol traces are given
+	let skip_entries = match config.entry_strategy {
+		EntryStrategy::Inline | EntryStrategy::Hoist => true,
+		EntryStrategy::Hook => config.symbol_traces.is_none(),
+		_ => false,
+	};
+	if !skip_entries {
+		let file_modules = final_output.modules.len();
+		final_output = generate_entries(
+			final_output,
+			&core_module,
+			config.explicit_extensions,
+			config.source_maps.then_some(&SourceMapOptions {
 				root_dir,
-				relative_path: relative_path.to_str().unwrap(),
-				minify: config.minify,
-				code: &code,
-				explicit_extensions: config.explicit_extensions,
-				source_maps: config.source_maps,
-				transpile_jsx: config.transpile_jsx,
-				transpile_ts: config.transpile_ts,
-				preserve_filenames: config.preserve_filenames,
-				scope: config.scope.as_ref(),
-				entry_policy,
-				mode: config.mode,
-				core_module: core_module.clone(),
-				entry_strategy: config.entry_strategy,
-				reg_ctx_name: config.reg_ctx_name.as_deref(),
-				strip_exports: config.strip_exports.as_deref(),
-				strip_ctx_name: config.strip_ctx_name.as_deref(),
-				strip_event_handlers: config.strip_event_handlers,
-				is_server: config.is_server,
-			})
-		})
-		.reduce(|| Ok(TransformOutput::new()), |x, y| Ok(x?.append(&mut y?)))?;
+				input_map: None,
+				inline: config.inline_source_maps,
+				sources_content: config.sources_content,
+			}),
+		)?;
+		if let Some(on_output) = on_output {
+			let entries = TransformOutput {
+				modules: final_output.modules.split_off(file_modules),
+				..TransformOutput::new()
+			};
+			final_output.modules.extend(entries.without_code().modules);
+			on_output(entries)?;
+		}
+	}
+	// final_output = generate_entries(
+	//     final_output,
+	//     &core_module,
+	//     config.explicit_extensions,
+	// )?;
+	if let Some(traces) = &config.symbol_traces {
+		final_output.trace_report = Some(traces.report(&final_output));
+	}
+	Ok(final_output)
+}
+
+pub fn transform_modules(
+	config: TransformModulesOptions,
+) -> Result<TransformOutput, TransformError> {
+	transform_modules_cached(config, None, None)
+}
+
+/// `transform_fs_with_cache` for in-memory inputs, their source map is part of the cache key.
+pub fn transform_modules_with_cache(
+	config: TransformModulesOptions,
+	cache: &TransformCache,
+) -> Result<TransformOutput, TransformError> {
+	transform_modules_cached(config, Some(cache), None)
+}
 
+/// `transform_fs_with_entry_policy` for in-memory inputs.
+pub fn transform_modules_with_entry_policy(
+	config: TransformModulesOptions,
+	entry_policy: &dyn EntryPolicy,
+) -> Result<TransformOutput, TransformError> {
+	transform_modules_cached(config, None, Some(entry_policy))
+}
+
+fn transform_modules_cached(
+	mut config: TransformModulesOptions,
+	cache: Option<&TransformCache>,
+	custom_policy: Option<&dyn EntryPolicy>,
+) -> Result<TransformOutput, TransformError> {
+	let options_key = if cache.is_some() {
+		let input = std::mem::take(&mut config.input);
+		let key = serde_json::to_value(&config)
+			.map_err(|err| TransformError::invalid_option("options", err))?
+			.to_string();
+		config.input = input;
+		key
+	} else {
+		String::new()
+	};
+	let core_module = config
+		.core_module
+		.map_or(BUILDER_IO_QWIK.clone(), |s| s.into());
+	let es_target = config
+		.es_target
+		.map_or(EsVersion::latest(), EsVersion::from);
+	let jsx_import_source = config
+		.jsx_import_source
+		.map_or_else(|| core_module.clone(), |s| s.into());
+	let jsx_pragma = config.jsx_pragma.map_or(H.clone(), |s| s.into());
+	let jsx_pragma_frag = config
+		.jsx_pragma_frag
+		.map_or(FRAGMENT.clone(), |s| s.into());
+	let define = Defines::new(&config.define.unwrap_or_default())?;
+	let src_dir = std::path::Path::new(&config.src_dir);
+	let root_dir = config.root_dir.as_ref().map(Path::new);
+
+	let entry_rules = EntryRules::new(config.entry_rules.as_deref().unwrap_or_default())?;
+	let transform_all =
+		|entry_policy: &dyn EntryPolicy, entries_key: &str, _: Option<&OnOutput>| {
+			let entry_policy = &entry_rules.with_fallback(entry_policy);
+			#[cfg(feature = "parallel")]
+			let iterator = config.input.par_iter();
+
+			#[cfg(not(feature = "parallel"))]
+			let iterator = config.input.iter();
+			let iterator = iterator.map(|path| -> Result<TransformOutput, TransformError> {
+				let transform = || {
+					transform_code(TransformCodeOptions {
+						src_dir,
+						root_dir,
+						relative_path: &path.path,
+						code: &path.code,
+						input_map: path.map.as_deref(),
+						minify: config.minify,
+						es_target,
+						source_maps: config.source_maps,
+						inline_source_maps: config.inline_source_maps,
+						sources_content: config.sources_content,
+						transpile_ts: config.transpile_ts,
+						transpile_jsx: config.transpile_jsx,
+						preserve_filenames: config.preserve_filenames,
+						explicit_extensions: config.explicit_extensions,
+						entry_policy,
+						mode: config.mode,
+						scope: config.scope.as_ref(),
+						core_module: core_module.clone(),
+						jsx_import_source: jsx_import_source.clone(),
+						jsx_pragma: jsx_pragma.clone(),
+						jsx_pragma_frag: jsx_pragma_frag.clone(),
+						define: &define,
+						entry_strategy: config.entry_strategy,
+						reg_ctx_name: config.reg_ctx_name.as_deref(),
+						strip_exports: config.strip_exports.as_deref(),
+						strip_ctx_name: config.strip_ctx_name.as_deref(),
+						strip_event_handlers: config.strip_event_handlers,
+						is_server: config.is_server,
+					})
+				};
+				match cache {
+					Some(cache) => cache.get_or_insert_with(
+						&[&options_key, entries_key].concat(),
+						&path.path,
+						&path.code,
+						path.map.as_deref(),
+						transform,
+					),
+					None => transform(),
+				}
+			});
+
+			#[cfg(feature = "parallel")]
+			let final_output: Result<TransformOutput, TransformError> =
+				iterator.reduce(|| Ok(TransformOutput::new()), |x, y| Ok(x?.append(&mut y?)));
+
+			#[cfg(not(feature = "parallel"))]
+			let final_output: Result<TransformOutput, TransformError> =
+				{ iterator }.try_fold(TransformOutput::new(), |x, y| Ok(x.append(&mut y?)));
+
+			final_output
+		};
+	let manual_chunks = with_trace_entries(config.manual_chunks, config.symbol_traces.as_ref());
+	let mut final_output = if let Some(entry_policy) = custom_policy {
+		transform_all(entry_policy, "", None)?
+	} else if matches!(config.entry_strategy, EntryStrategy::Sized) {
+		transform_sized(
+			manual_chunks,
+			config.sized_chunks.as_ref(),
+			None,
+			transform_all,
+		)?
+	} else {
+		transform_all(
+			&*parse_entry_strategy(&config.entry_strategy, manual_chunks),
+			"",
+			None,
+		)?
+	};
 	final_output.modules.sort_unstable_by_key(|key| key.order);
-	if !matches!(
-		config.entry_strategy,
-		EntryStrategy::Hook | EntryStrategy::Inline | EntryStrategy::Hoist
-	) {
+	// Hooks are only grouped into entries by the `Hook` strategy when symbol traces are given
+	let skip_entries = match config.entry_strategy {
+		EntryStrategy::Inline | EntryStrategy::Hoist => true,
+		EntryStrategy::Hook => config.symbol_traces.is_none(),
+		_ => false,
+	};
+	if !skip_entries {
 		final_output = generate_entries(
 			final_output,
 			&core_module,
 			config.explicit_extensions,
-			root_dir,
+			config.source_maps.then_some(&SourceMapOptions {
+				root_dir,
+				input_map: None,
+				inline: config.inline_source_maps,
+				sources_content: config.sources_content,
+			}),
 		)?;
 	}
 	// final_output = generate_entries(
 	//     final_output,
 	//     &core_module,
 	//     config.explicit_extensions,
-	//     root_dir,
 	// )?;
+	if let Some(traces) = &config.symbol_traces {
+		final_output.trace_report = Some(traces.report(&final_output));
+	}
+
 	Ok(final_output)
 }
 
-pub fn transform_modules(config: TransformModulesOptions) -> Result<TransformOutput, Error> {
+/// Adds the entries grouping the symbols loaded together in `traces` to the manual chunks, entries
+/// given in `manual_chunks` take precedence.
+fn with_trace_entries(
+	manual_chunks: Option<HashMap<String, JsWord>>,
+	traces: Option<&SymbolTraces>,
+) -> Option<HashMap<String, JsWord>> {
+	let Some(traces) = traces else {
+		return manual_chunks;
+	};
+	let mut entries = traces.entries();
+	entries.extend(manual_chunks.unwrap_or_default());
+	Some(entries)
+}
+
+/// Transforms twice for the `Sized` strategy: the first pass emits every hook in its own file to
+/// measure it, the second pass imports the hooks from the entries packed with those sizes.
+fn transform_sized(
+	manual_chunks: Option<HashMap<String, JsWord>>,
+	sized_chunks: Option<&SizedChunks>,
+	on_output: Option<&OnOutput>,
+	transform_all: impl Fn(
+		&dyn EntryPolicy,
+		&str,
+		Option<&OnOutput>,
+	) -> Result<TransformOutput, TransformError>,
+) -> Result<TransformOutput, TransformError> {
+	// Only the files of the second pass are streamed
+	let measured = transform_all(&PerHookStrategy::new(manual_chunks.clone()), "", None)?;
+	let hooks: Vec<_> = measured
+		.modules
+		.iter()
+		.filter_map(|module| {
+			let hook = module.hook.as_ref().filter(|hook| hook.entry.is_none())?;
+			Some((hook, module.code.len()))
+		})
+		.collect();
+	let mut entries = pack_hooks(&hooks, &sized_chunks.cloned().unwrap_or_default());
+	// The packed entries are part of the options of the second pass
+	let entries_key = serde_json::to_string(&entries.iter().collect::<BTreeMap<_, _>>())
+		.map_err(|err| TransformError::invalid_option("sizedChunks", err))?;
+	entries.extend(manual_chunks.unwrap_or_default());
+	transform_all(
+		&PerHookStrategy::new(Some(entries)),
+		&entries_key,
+		on_output,
+	)
+}
+
+/// Finds the hooks of every input module, what they capture and the diagnostics, like
+/// `transform_modules` does, but without generating any code.
+pub fn analyze_modules(config: AnalyzeModulesOptions) -> Result<AnalyzeOutput, TransformError> {
 	let core_module = config
 		.core_module
 		.map_or(BUILDER_IO_QWIK.clone(), |s| s.into());
 	let src_dir = std::path::Path::new(&config.src_dir);
 	let root_dir = config.root_dir.as_ref().map(Path::new);
+	let define = Defines::new(&config.define.unwrap_or_default())?;
+	let jsx_import_source = config
+		.jsx_import_source
+		.map_or_else(|| core_module.clone(), |s| s.into());
+	let jsx_pragma = config.jsx_pragma.map_or(H.clone(), |s| s.into());
+	let jsx_pragma_frag = config
+		.jsx_pragma_frag
+		.map_or(FRAGMENT.clone(), |s| s.into());
 
-	let entry_policy = &*parse_entry_strategy(&config.entry_strategy, config.manual_chunks);
+	let entry_policy = &*parse_entry_strategy(&EntryStrategy::Hook, None);
 	#[cfg(feature = "parallel")]
 	let iterator = config.input.par_iter();
 
 	#[cfg(not(feature = "parallel"))]
 	let iterator = config.input.iter();
-	let iterator = iterator.map(|path| -> Result<TransformOutput, Error> {
-		transform_code(TransformCodeOptions {
+	let iterator = iterator.map(|path| -> Result<AnalyzeOutput, TransformError> {
+		analyze_code(TransformCodeOptions {
 			src_dir,
 			root_dir,
 			relative_path: &path.path,
 			code: &path.code,
-			minify: config.minify,
-			source_maps: config.source_maps,
-			transpile_ts: config.transpile_ts,
-			transpile_jsx: config.transpile_jsx,
-			preserve_filenames: config.preserve_filenames,
-			explicit_extensions: config.explicit_extensions,
+			input_map: None,
+			minify: MinifyMode::None,
+			es_target: EsVersion::latest(),
+			source_maps: false,
+			inline_source_maps: false,
+			sources_content: false,
+			transpile_ts: true,
+			transpile_jsx: true,
+			preserve_filenames: false,
+			explicit_extensions: false,
 			entry_policy,
 			mode: config.mode,
 			scope: config.scope.as_ref(),
 			core_module: core_module.clone(),
-			entry_strategy: config.entry_strategy,
-			reg_ctx_name: config.reg_ctx_name.as_deref(),
-			strip_exports: config.strip_exports.as_deref(),
-			strip_ctx_name: config.strip_ctx_name.as_deref(),
-			strip_event_handlers: config.strip_event_handlers,
+			jsx_import_source: jsx_import_source.clone(),
+			jsx_pragma: jsx_pragma.clone(),
+			jsx_pragma_frag: jsx_pragma_frag.clone(),
+			define: &define,
+			entry_strategy: EntryStrategy::Hook,
+			reg_ctx_name: None,
+			strip_exports: None,
+			strip_ctx_name: None,
+			strip_event_handlers: false,
 			is_server: config.is_server,
 		})
 	});
 
 	#[cfg(feature = "parallel")]
-	let final_output: Result<TransformOutput, Error> =
-		iterator.reduce(|| Ok(TransformOutput::new()), |x, y| Ok(x?.append(&mut y?)));
+	let final_output: Result<AnalyzeOutput, TransformError> =
+		iterator.reduce(|| Ok(AnalyzeOutput::new()), |x, y| Ok(x?.append(&mut y?)));
 
 	#[cfg(not(feature = "parallel"))]
-	let final_output: Result<TransformOutput, Error> =
-		iterator.fold(Ok(TransformOutput::new()), |x, y| Ok(x?.append(&mut y?)));
-
-	let mut final_output = final_output?;
-	final_output.modules.sort_unstable_by_key(|key| key.order);
-	if !matches!(
-		config.entry_strategy,
-		EntryStrategy::Hook | EntryStrategy::Inline | EntryStrategy::Hoist
-	) {
-		final_output = generate_entries(
-			final_output,
-			&core_module,
-			config.explicit_extensions,
-			root_dir,
-		)?;
-	}
-	// final_output = generate_entries(
-	//     final_output,
-	//     &core_module,
-	//     config.explicit_extensions,
-	//     root_dir,
-	// )?;
+	let final_output: Result<AnalyzeOutput, TransformError> =
+		iterator.fold(Ok(AnalyzeOutput::new()), |x, y| Ok(x?.append(&mut y?)));
 
-	Ok(final_output)
+	final_output
 }
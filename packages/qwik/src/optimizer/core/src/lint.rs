@@ -0,0 +1,284 @@
+use crate::collector::{GlobalCollect, Id};
+use crate::errors;
+use crate::words::*;
+use swc_atoms::JsWord;
+use swc_common::errors::HANDLER;
+use swc_common::Span;
+use swc_ecmascript::ast;
+use swc_ecmascript::visit::{noop_visit_type, Visit, VisitWith};
+
+macro_rules! id {
+	($ident: expr) => {
+		($ident.sym.clone(), $ident.span.ctxt())
+	};
+}
+
+/// Object and array literals with more entries than this are considered large captures.
+const LARGE_LITERAL_ENTRIES: usize = 32;
+
+/// Constructors whose instances Qwik knows how to serialize.
+const SERIALIZABLE_CONSTRUCTORS: &[&str] = &[
+	"Date",
+	"URL",
+	"URLSearchParams",
+	"RegExp",
+	"Map",
+	"Set",
+	"FormData",
+	"Error",
+	"Uint8Array",
+];
+
+/// Describes why capturing a declaration inside a Qrl($) scope is suspicious.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureLint {
+	/// An object or array literal declared with `let` or `var`.
+	MutableObject,
+	/// An object or array literal with many entries.
+	LargeObject,
+	/// A function or an arrow function.
+	Function,
+	/// An instance of a class that can not be serialized.
+	ClassInstance,
+}
+
+impl CaptureLint {
+	pub fn classify(kind: ast::VarDeclKind, init: Option<&ast::Expr>) -> Option<Self> {
+		match init? {
+			ast::Expr::Paren(paren) => Self::classify(kind, Some(&paren.expr)),
+			ast::Expr::Arrow(_) | ast::Expr::Fn(_) => Some(Self::Function),
+			ast::Expr::Class(_) => Some(Self::ClassInstance),
+			ast::Expr::New(new_expr) => match &*new_expr.callee {
+				ast::Expr::Ident(ident)
+					if SERIALIZABLE_CONSTRUCTORS.contains(&ident.sym.as_ref()) =>
+				{
+					None
+				}
+				_ => Some(Self::ClassInstance),
+			},
+			expr @ (ast::Expr::Object(_) | ast::Expr::Array(_)) => {
+				if kind != ast::VarDeclKind::Const {
+					Some(Self::MutableObject)
+				} else if count_entries(expr) > LARGE_LITERAL_ENTRIES {
+					Some(Self::LargeObject)
+				} else {
+					None
+				}
+			}
+			_ => None,
+		}
+	}
+
+	pub const fn error(self) -> errors::Error {
+		match self {
+			Self::MutableObject | Self::LargeObject => errors::Error::CaptureMutableObject,
+			Self::Function | Self::ClassInstance => errors::Error::CaptureNonSerializable,
+		}
+	}
+
+	pub fn message(self, name: &str) -> String {
+		match self {
+			Self::MutableObject => format!(
+				"Qrl($) scope is capturing '{}', a mutable object. It's serialized when the Qrl is created, later changes to it will not be visible inside the Qrl($) scope",
+				name
+			),
+			Self::LargeObject => format!(
+				"Qrl($) scope is capturing '{}', a large object. The whole object is serialized into the HTML",
+				name
+			),
+			Self::Function => format!(
+				"Qrl($) scope is capturing '{}', a function that can not be serialized. Wrap it with $() to turn it into a Qrl",
+				name
+			),
+			Self::ClassInstance => format!(
+				"Qrl($) scope is capturing '{}', a class instance that can not be serialized",
+				name
+			),
+		}
+	}
+}
+
+fn count_entries(expr: &ast::Expr) -> usize {
+	match expr {
+		ast::Expr::Object(obj) => obj
+			.props
+			.iter()
+			.map(|prop| match prop {
+				ast::PropOrSpread::Prop(box ast::Prop::KeyValue(kv)) => {
+					1 + count_entries(&kv.value)
+				}
+				_ => 1,
+			})
+			.sum(),
+		ast::Expr::Array(arr) => arr
+			.elems
+			.iter()
+			.flatten()
+			.map(|elem| 1 + count_entries(&elem.expr))
+			.sum(),
+		_ => 0,
+	}
+}
+
+/// Edits that wrap a function declaration with `$()`, so it becomes a `Qrl` that can be
+/// captured. `dollar` is the local name of the `$` import. Generators can not be arrow functions
+/// and are left alone.
+pub fn fn_decl_to_qrl(node: &ast::FnDecl, dollar: &str) -> Option<Vec<(Span, String)>> {
+	let function = &node.function;
+	let body = function.body.as_ref()?;
+	if function.is_generator || function.span.is_dummy() || body.span.is_dummy() {
+		return None;
+	}
+	let header = function.span.with_hi(node.ident.span.hi);
+	let header_text = if function.is_async {
+		format!("const {} = {}(async ", node.ident.sym, dollar)
+	} else {
+		format!("const {} = {}(", node.ident.sym, dollar)
+	};
+	Some(vec![
+		(header, header_text),
+		(body.span.shrink_to_lo(), "=> ".to_string()),
+		(function.span.shrink_to_hi(), ");".to_string()),
+	])
+}
+
+/// Source code of an exported `Qrl` implementation that only throws, used as a starting point
+/// when the implementation of a `$` marker function is missing.
+pub fn qrl_implementation_stub(name: &str, is_type_script: bool) -> String {
+	let param = if is_type_script {
+		"qrl: unknown"
+	} else {
+		"qrl"
+	};
+	format!(
+		"export const {name} = ({param}) => {{\n\tthrow new Error('{name}() is not implemented');\n}};\n\n"
+	)
+}
+
+/// Warns about tasks that are not always registered, because they are called under
+/// a condition, inside a loop or after an early return.
+pub fn lint_conditional_tasks(
+	module: &ast::Module,
+	global_collect: &GlobalCollect,
+	core_module: &JsWord,
+) {
+	let task_fns: Vec<Id> = [&*USE_TASK, &*USE_VISIBLE_TASK]
+		.into_iter()
+		.filter_map(|name| global_collect.get_imported_local(name, core_module))
+		.collect();
+	if task_fns.is_empty() {
+		return;
+	}
+	let mut linter = ConditionalTaskLinter {
+		task_fns,
+		under_conditional: 0,
+		found_return: false,
+	};
+	module.visit_with(&mut linter);
+}
+
+struct ConditionalTaskLinter {
+	task_fns: Vec<Id>,
+	under_conditional: i32,
+	found_return: bool,
+}
+
+impl ConditionalTaskLinter {
+	fn visit_conditional<T: VisitWith<Self>>(&mut self, node: &T) {
+		self.under_conditional += 1;
+		node.visit_children_with(self);
+		self.under_conditional -= 1;
+	}
+
+	fn visit_function_body<T: VisitWith<Self>>(&mut self, node: &T) {
+		let under_conditional = self.under_conditional;
+		let found_return = self.found_return;
+		self.under_conditional = 0;
+		self.found_return = false;
+		node.visit_children_with(self);
+		self.under_conditional = under_conditional;
+		self.found_return = found_return;
+	}
+}
+
+impl Visit for ConditionalTaskLinter {
+	noop_visit_type!();
+
+	fn visit_arrow_expr(&mut self, node: &ast::ArrowExpr) {
+		self.visit_function_body(node);
+	}
+
+	fn visit_function(&mut self, node: &ast::Function) {
+		self.visit_function_body(node);
+	}
+
+	fn visit_return_stmt(&mut self, node: &ast::ReturnStmt) {
+		node.visit_children_with(self);
+		self.found_return = true;
+	}
+
+	fn visit_for_in_stmt(&mut self, node: &ast::ForInStmt) {
+		self.visit_conditional(node);
+	}
+
+	fn visit_for_of_stmt(&mut self, node: &ast::ForOfStmt) {
+		self.visit_conditional(node);
+	}
+
+	fn visit_for_stmt(&mut self, node: &ast::ForStmt) {
+		self.visit_conditional(node);
+	}
+
+	fn visit_if_stmt(&mut self, node: &ast::IfStmt) {
+		self.visit_conditional(node);
+	}
+
+	fn visit_while_stmt(&mut self, node: &ast::WhileStmt) {
+		self.visit_conditional(node);
+	}
+
+	fn visit_do_while_stmt(&mut self, node: &ast::DoWhileStmt) {
+		self.visit_conditional(node);
+	}
+
+	fn visit_switch_stmt(&mut self, node: &ast::SwitchStmt) {
+		self.visit_conditional(node);
+	}
+
+	fn visit_cond_expr(&mut self, node: &ast::CondExpr) {
+		self.visit_conditional(node);
+	}
+
+	fn visit_bin_expr(&mut self, node: &ast::BinExpr) {
+		if matches!(
+			node.op,
+			ast::BinaryOp::LogicalAnd | ast::BinaryOp::LogicalOr | ast::BinaryOp::NullishCoalescing
+		) {
+			self.visit_conditional(node);
+		} else {
+			node.visit_children_with(self);
+		}
+	}
+
+	fn visit_call_expr(&mut self, node: &ast::CallExpr) {
+		if self.under_conditional > 0 || self.found_return {
+			if let ast::Callee::Expr(box ast::Expr::Ident(ident)) = &node.callee {
+				if self.task_fns.contains(&id!(ident)) {
+					HANDLER.with(|handler| {
+						handler
+							.struct_span_warn_with_code(
+								node.span,
+								&format!(
+									"{}() is called conditionally, it will only be registered when the condition is met. Move the condition inside the task",
+									ident.sym
+								),
+								errors::get_diagnostic_id(errors::Error::ConditionalTask),
+							)
+							.emit();
+					});
+				}
+			}
+		}
+		node.visit_children_with(self);
+	}
+}
@@ -0,0 +1,47 @@
+use swc_common::comments::Comments;
+use swc_common::{sync::Lrc, Mark, SourceMap};
+use swc_ecmascript::ast;
+use swc_ecmascript::minifier::optimize;
+use swc_ecmascript::minifier::option::{
+	CompressOptions, ExtraOptions, MangleOptions, MinifyOptions,
+};
+
+/// Compresses the module and mangles its local variables with the swc minifier.
+/// Top level declarations keep their names, they are the exports and imports of the module.
+///
+/// Needs the marks given to the resolver, so it must run before `hygiene`.
+pub fn minify(
+	module: ast::Module,
+	source_map: Lrc<SourceMap>,
+	comments: Option<&dyn Comments>,
+	unresolved_mark: Mark,
+	top_level_mark: Mark,
+	target: ast::EsVersion,
+) -> ast::Module {
+	let program = optimize(
+		ast::Program::Module(module),
+		source_map,
+		comments,
+		None,
+		&MinifyOptions {
+			compress: Some(CompressOptions {
+				ecma: target,
+				module: true,
+				..Default::default()
+			}),
+			mangle: Some(MangleOptions {
+				top_level: Some(false),
+				..Default::default()
+			}),
+			..Default::default()
+		},
+		&ExtraOptions {
+			unresolved_mark,
+			top_level_mark,
+		},
+	);
+	match program {
+		ast::Program::Module(module) => module,
+		ast::Program::Script(_) => unreachable!("the minifier keeps modules as modules"),
+	}
+}
@@ -1,41 +1,132 @@
 pub use crate::entry_strategy::EntryStrategy;
 pub use crate::parse::{ErrorBuffer, HookAnalysis, MinifyMode, TransformModule, TransformOutput};
 
+#[cfg(feature = "fs")]
+use crate::errors::TransformError;
+#[cfg(feature = "fs")]
+use glob::{MatchOptions, Pattern};
+#[cfg(feature = "fs")]
+use path_slash::PathExt;
+#[cfg(feature = "fs")]
+use serde::{Deserialize, Serialize};
+
+/// The `glob` option, a single pattern or a list of them.
+#[cfg(feature = "fs")]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum Globs {
+	One(String),
+	Many(Vec<String>),
+}
+
+#[cfg(feature = "fs")]
+impl Globs {
+	pub fn as_slice(&self) -> &[String] {
+		match self {
+			Self::One(glob) => std::slice::from_ref(glob),
+			Self::Many(globs) => globs,
+		}
+	}
+}
+
+/// Include and exclude patterns applied to the path of every discovered file, relative to the
+/// root it was found in. Patterns starting with `!` exclude files, the rest include them.
+/// When there are no include patterns, every file is included.
+#[cfg(feature = "fs")]
+#[derive(Default)]
+pub struct FileFilter {
+	include: Vec<Pattern>,
+	exclude: Vec<Pattern>,
+}
+
+#[cfg(feature = "fs")]
+impl FileFilter {
+	pub fn new(globs: &[String]) -> Result<Self, TransformError> {
+		let mut filter = Self::default();
+		for glob in globs {
+			let (list, glob) = match glob.strip_prefix('!') {
+				Some(glob) => (&mut filter.exclude, glob),
+				None => (&mut filter.include, glob.as_str()),
+			};
+			let glob = glob.strip_prefix("./").unwrap_or(glob);
+			let pattern = Pattern::new(glob).map_err(|err| {
+				TransformError::invalid_option("glob", format!("{}: {}", glob, err))
+			})?;
+			list.push(pattern);
+		}
+		Ok(filter)
+	}
+
+	fn matches(&self, relative_path: &std::path::Path) -> bool {
+		const OPTIONS: MatchOptions = MatchOptions {
+			case_sensitive: true,
+			require_literal_separator: true,
+			require_literal_leading_dot: false,
+		};
+		let relative_path = relative_path.to_slash_lossy();
+		let matches = |pattern: &Pattern| pattern.matches_with(&relative_path, OPTIONS);
+		(self.include.is_empty() || self.include.iter().any(matches))
+			&& !self.exclude.iter().any(matches)
+	}
+}
+
 #[cfg(feature = "fs")]
 pub fn find_modules(
 	src_dir: &std::path::Path,
 	vendor_dirs: Vec<String>,
+	filter: &FileFilter,
 	files: &mut Vec<std::path::PathBuf>,
-) -> std::io::Result<()> {
+) -> Result<(), TransformError> {
 	for root in &vendor_dirs {
-		find_files(std::path::Path::new(root), files)?;
+		let root = std::path::Path::new(root);
+		find_files(root, root, filter, files)?;
 	}
-	find_files(src_dir, files)
+	find_files(src_dir, src_dir, filter, files)
 }
 
 #[cfg(feature = "fs")]
-fn find_files(dir: &std::path::Path, files: &mut Vec<std::path::PathBuf>) -> std::io::Result<()> {
+fn find_files(
+	root: &std::path::Path,
+	dir: &std::path::Path,
+	filter: &FileFilter,
+	files: &mut Vec<std::path::PathBuf>,
+) -> Result<(), TransformError> {
 	if dir.is_dir() {
-		for entry in std::fs::read_dir(dir)? {
-			let entry = entry?;
+		let entries = std::fs::read_dir(dir).map_err(|err| TransformError::io(dir, err))?;
+		for entry in entries {
+			let entry = entry.map_err(|err| TransformError::io(dir, err))?;
 			let path = entry.path();
 			if path.is_dir() {
-				find_files(&path, files)?;
-			} else if should_capture_file(&path) {
+				find_files(root, &path, filter, files)?;
+			} else if should_capture_file(root, &path, filter) {
 				files.push(path);
 			}
 		}
-	} else if should_capture_file(dir) {
+	} else if should_capture_file(root, dir, filter) {
 		files.push(dir.to_path_buf());
 	}
 	Ok(())
 }
 
 #[cfg(feature = "fs")]
-fn should_capture_file(path: &std::path::Path) -> bool {
+fn should_capture_file(
+	root: &std::path::Path,
+	path: &std::path::Path,
+	filter: &FileFilter,
+) -> bool {
 	let ext = path.extension().and_then(|p| p.to_str());
-	matches!(
+	if !matches!(
 		ext,
 		Some("ts" | "tsx" | "js" | "jsx" | "mjs" | "mts" | "mtsx" | "mjsx")
-	)
+	) {
+		return false;
+	}
+	let relative_path = path.strip_prefix(root).unwrap_or(path);
+	let relative_path = if relative_path.as_os_str().is_empty() {
+		// The root is the file itself
+		path.file_name().map_or(path, std::path::Path::new)
+	} else {
+		relative_path
+	};
+	filter.matches(relative_path)
 }
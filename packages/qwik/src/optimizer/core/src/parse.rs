@@ -1,5 +1,5 @@
-use std::collections::hash_map::DefaultHasher;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
 use std::hash::Hasher;
 use std::path::{Component, Path, PathBuf};
@@ -8,26 +8,40 @@ use std::str;
 use crate::add_side_effect::SideEffectVisitor;
 use crate::clean_side_effects::Treeshaker;
 use crate::code_move::{new_module, NewModuleCtx};
-use crate::collector::global_collect;
-use crate::const_replace::ConstReplacerVisitor;
+use crate::collector::{collect_relative_imports, global_collect, BindingCollector, Id};
+use crate::const_replace::{ConstReplacerVisitor, DefineReplacer, Defines};
+use crate::downlevel::downlevel;
 use crate::entry_strategy::EntryPolicy;
-use crate::filter_exports::StripExportsVisitor;
+use crate::errors::{self, TransformError};
+use crate::filter_exports::{
+	collect_used_server_imports, remove_unused_declarations, server_only_imports,
+	strip_server_code, used_top_level_names, StripExportsVisitor,
+};
+use crate::hash::StableHasher;
+use crate::lint::lint_conditional_tasks;
+use crate::minify::minify;
 use crate::props_destructuring::transform_props_destructuring;
-use crate::transform::{HookKind, QwikTransform, QwikTransformOptions};
-use crate::utils::{Diagnostic, DiagnosticCategory, DiagnosticScope, SourceLocation};
+use crate::trace::TraceReport;
+use crate::transform::{Hook, HookKind, QwikTransform, QwikTransformOptions};
+use crate::utils::{
+	Diagnostic, DiagnosticCategory, DiagnosticEdit, DiagnosticFix, DiagnosticScope, SourceLocation,
+};
 use crate::EntryStrategy;
+use base64::Engine;
 use path_slash::PathExt;
+use relative_path::RelativePath;
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "fs")]
 use std::fs;
 
-use anyhow::{Context, Error};
+use anyhow::Error;
 
 use swc_atoms::JsWord;
-use swc_common::comments::SingleThreadedComments;
-use swc_common::errors::{DiagnosticBuilder, DiagnosticId, Emitter, Handler};
-use swc_common::{sync::Lrc, FileName, Globals, Mark, SourceMap};
+use swc_common::comments::{Comment, CommentKind, Comments, SingleThreadedComments};
+use swc_common::errors::{DiagnosticBuilder, DiagnosticId, Emitter, Handler, Level, HANDLER};
+use swc_common::source_map::SourceMapGenConfig;
+use swc_common::{sync::Lrc, BytePos, FileName, Globals, Mark, SourceMap, Span, Spanned};
 use swc_ecmascript::ast;
 use swc_ecmascript::codegen::text_writer::JsWriter;
 use swc_ecmascript::parser::lexer::Lexer;
@@ -35,7 +49,7 @@ use swc_ecmascript::parser::{EsConfig, PResult, Parser, StringInput, Syntax, TsC
 use swc_ecmascript::transforms::{
 	fixer, hygiene::hygiene_with_config, optimization::simplify, react, resolver, typescript,
 };
-use swc_ecmascript::visit::{FoldWith, VisitMutWith};
+use swc_ecmascript::visit::{FoldWith, VisitMutWith, VisitWith};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
@@ -52,12 +66,15 @@ pub struct HookAnalysis {
 	pub ctx_kind: HookKind,
 	pub ctx_name: JsWord,
 	pub captures: bool,
-	pub loc: (u32, u32),
+	pub loc: SourceLocation,
 }
 
 #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub enum MinifyMode {
+	/// Compresses the code and mangles local variables with the swc minifier, and emits without
+	/// whitespace.
+	Minify,
 	Simplify,
 	None,
 }
@@ -75,17 +92,25 @@ pub struct TransformCodeOptions<'a> {
 	pub src_dir: &'a Path,
 	pub root_dir: Option<&'a Path>,
 	pub source_maps: bool,
+	pub inline_source_maps: bool,
+	pub sources_content: bool,
 	pub minify: MinifyMode,
+	pub es_target: ast::EsVersion,
 	pub transpile_ts: bool,
 	pub transpile_jsx: bool,
 	pub preserve_filenames: bool,
 	pub explicit_extensions: bool,
 	pub code: &'a str,
+	pub input_map: Option<&'a str>,
 	pub entry_policy: &'a dyn EntryPolicy,
 	pub mode: EmitMode,
 	pub scope: Option<&'a String>,
 	pub entry_strategy: EntryStrategy,
 	pub core_module: JsWord,
+	pub jsx_import_source: JsWord,
+	pub jsx_pragma: JsWord,
+	pub jsx_pragma_frag: JsWord,
+	pub define: &'a Defines,
 
 	pub reg_ctx_name: Option<&'a [JsWord]>,
 	pub strip_exports: Option<&'a [JsWord]>,
@@ -94,13 +119,57 @@ pub struct TransformCodeOptions<'a> {
 	pub is_server: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct TransformOutput {
 	pub modules: Vec<TransformModule>,
 	pub diagnostics: Vec<Diagnostic>,
 	pub is_type_script: bool,
 	pub is_jsx: bool,
+	/// Requests the recorded symbol traces would make, when traces are given.
+	pub trace_report: Option<TraceReport>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyzeOutput {
+	pub modules: Vec<ModuleAnalysis>,
+	pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleAnalysis {
+	pub path: JsWord,
+	pub hooks: Vec<AnalyzedHook>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyzedHook {
+	pub hook: HookAnalysis,
+	/// Identifiers from the enclosing scopes that are captured by the hook.
+	pub captured: Vec<CapturedIdent>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CapturedIdent {
+	pub name: JsWord,
+	/// Where the identifier is declared.
+	pub loc: Option<SourceLocation>,
+}
+
+impl AnalyzeOutput {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn append(mut self, output: &mut Self) -> Self {
+		self.modules.append(&mut output.modules);
+		self.diagnostics.append(&mut output.diagnostics);
+		self
+	}
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -108,6 +177,12 @@ pub struct TransformOutput {
 pub struct QwikBundle {
 	pub size: usize,
 	pub symbols: Vec<JsWord>,
+	/// Bundles imported statically, they are needed before this bundle can run.
+	pub imports: Vec<JsWord>,
+	/// Bundles imported by the QRLs in this bundle, they are needed when a QRL is invoked.
+	pub dynamic_imports: Vec<JsWord>,
+	/// Source files contributing code to this bundle.
+	pub origins: Vec<JsWord>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -132,6 +207,35 @@ impl TransformOutput {
 		self
 	}
 
+	/// A copy of the output without the code and source maps of its modules, only what the
+	/// manifest and the entries need: their hooks, imports and the length of their code.
+	pub fn without_code(&self) -> Self {
+		Self {
+			modules: self
+				.modules
+				.iter()
+				.map(|module| TransformModule {
+					path: module.path.clone(),
+					code: String::new(),
+					map: None,
+					hook: module.hook.clone(),
+					is_entry: module.is_entry,
+					orig_path: module.orig_path.clone(),
+					imports: module.imports.clone(),
+					dynamic_imports: module.dynamic_imports.clone(),
+					order: module.order,
+					code_len: Some(module.code_len.unwrap_or(module.code.len())),
+				})
+				.collect(),
+			diagnostics: self.diagnostics.clone(),
+			is_type_script: self.is_type_script,
+			is_jsx: self.is_jsx,
+			trace_report: self.trace_report.clone(),
+		}
+	}
+
+	/// Describes the bundles of the output: hooks that belong to an entry are bundled together
+	/// with the entry module that re-exports them, every other module is a bundle on its own.
 	pub fn get_manifest(&self) -> QwikManifest {
 		let mut manifest = QwikManifest {
 			bundles: HashMap::new(),
@@ -139,48 +243,130 @@ impl TransformOutput {
 			mapping: HashMap::new(),
 			version: "1".into(),
 		};
-		for module in &self.modules {
+
+		// Modules can be imported with or without their extension
+		let mut module_index: HashMap<&str, usize> = HashMap::with_capacity(self.modules.len() * 2);
+		for (index, module) in self.modules.iter().enumerate() {
+			module_index.insert(&module.path, index);
+			if let Some((stem, _)) = module.path.rsplit_once('.') {
+				module_index.entry(stem).or_insert(index);
+			}
+		}
+		let bundle_names: Vec<JsWord> = self
+			.modules
+			.iter()
+			.map(|module| {
+				module
+					.hook
+					.as_ref()
+					.and_then(|hook| hook.entry.as_ref())
+					.map(|entry| [entry, ".js"].concat())
+					.filter(|entry| module_index.contains_key(entry.as_str()))
+					.unwrap_or_else(|| module.path.clone())
+					.into()
+			})
+			.collect();
+		let resolve = |from: &str, specifier: &str| {
+			let path = RelativePath::new(from).parent()?.join_normalized(specifier);
+			module_index
+				.get(path.as_str())
+				.map(|index| &bundle_names[*index])
+		};
+
+		for (module, bundle_name) in self.modules.iter().zip(&bundle_names) {
+			let bundle = manifest.bundles.entry(bundle_name.clone()).or_default();
+			bundle.size += module.code_len.unwrap_or(module.code.len());
 			if let Some(hook) = &module.hook {
-				let filename =
-					JsWord::from(format!("{}.{}", hook.canonical_filename, hook.extension));
-				manifest.mapping.insert(hook.name.clone(), filename.clone());
+				bundle.symbols.push(hook.name.clone());
+				manifest
+					.mapping
+					.insert(hook.name.clone(), bundle_name.clone());
 				manifest.symbols.insert(hook.name.clone(), hook.clone());
-				manifest.bundles.insert(
-					filename.clone(),
-					QwikBundle {
-						symbols: vec![hook.name.clone()],
-						size: module.code.len(),
-					},
+			}
+			if let Some(orig_path) = &module.orig_path {
+				bundle.origins.push(orig_path.clone());
+			}
+			for (specifiers, bundles) in [
+				(&module.imports, &mut bundle.imports),
+				(&module.dynamic_imports, &mut bundle.dynamic_imports),
+			] {
+				bundles.extend(
+					specifiers
+						.iter()
+						.filter_map(|specifier| resolve(&module.path, specifier))
+						.filter(|imported| *imported != bundle_name)
+						.cloned(),
 				);
 			}
 		}
+
+		for bundle in manifest.bundles.values_mut() {
+			for names in [
+				&mut bundle.symbols,
+				&mut bundle.imports,
+				&mut bundle.dynamic_imports,
+				&mut bundle.origins,
+			] {
+				names.sort();
+				names.dedup();
+			}
+		}
 		manifest
 	}
 
+	/// Writes the modules to `destination`. When `source_map_file` is given, the source maps of
+	/// the modules are written next to them, in the file it names, and the modules get a
+	/// `sourceMappingURL` comment. `[file]` stands for the file name of the module.
 	#[cfg(feature = "fs")]
 	pub fn write_to_fs(
 		&self,
 		destination: &Path,
 		manifest: Option<String>,
-	) -> Result<usize, Error> {
+		source_map_file: Option<&str>,
+	) -> Result<usize, TransformError> {
 		for module in &self.modules {
 			let write_path = destination.join(&module.path);
-			fs::create_dir_all(write_path.parent().with_context(|| {
-				format!("Computing path parent of {}", write_path.to_string_lossy())
-			})?)?;
-			fs::write(write_path, &module.code)?;
+			let parent = write_path
+				.parent()
+				.ok_or_else(|| TransformError::invalid_path(&write_path, "has no parent"))?;
+			fs::create_dir_all(parent).map_err(|err| TransformError::io(parent, err))?;
+			let code = match (source_map_file, &module.map) {
+				(Some(pattern), Some(_)) => [
+					module.code.as_str(),
+					"\n//# sourceMappingURL=",
+					&module.source_map_file(pattern),
+					"\n",
+				]
+				.concat(),
+				_ => module.code.clone(),
+			};
+			fs::write(&write_path, code).map_err(|err| TransformError::io(&write_path, err))?;
+			if let (Some(pattern), Some(map)) = (source_map_file, &module.map) {
+				let map_path = parent.join(module.source_map_file(pattern));
+				let map =
+					relocate_source_map(map, &module.path, map_path.parent().unwrap_or(parent))
+						.map_err(|err| TransformError::emit(&map_path, err))?;
+				fs::write(&map_path, map).map_err(|err| TransformError::io(&map_path, err))?;
+			}
 		}
 		if let Some(manifest) = manifest {
-			let write_path = destination.join(manifest);
-			let manifest = self.get_manifest();
-			let json = serde_json::to_string(&manifest)?;
-			fs::write(write_path, json)?;
+			self.write_manifest(destination, &manifest)?;
 		}
 		Ok(self.modules.len())
 	}
+
+	/// Writes the manifest of the output to the `manifest` file in `destination`.
+	#[cfg(feature = "fs")]
+	pub fn write_manifest(&self, destination: &Path, manifest: &str) -> Result<(), TransformError> {
+		let write_path = destination.join(manifest);
+		let manifest = self.get_manifest();
+		let json = serde_json::to_string(&manifest)
+			.map_err(|err| TransformError::emit(&write_path, err))?;
+		fs::write(&write_path, json).map_err(|err| TransformError::io(&write_path, err))
+	}
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct TransformModule {
 	pub path: String,
@@ -191,8 +377,63 @@ pub struct TransformModule {
 	pub hook: Option<HookAnalysis>,
 	pub is_entry: bool,
 
-	#[serde(skip_serializing)]
+	/// The source file the module was created from, relative to the source directory.
+	pub orig_path: Option<JsWord>,
+	/// Relative specifiers of the modules imported statically.
+	pub imports: Vec<JsWord>,
+	/// Relative specifiers of the modules imported with `import()`, the QRLs.
+	pub dynamic_imports: Vec<JsWord>,
+
+	#[serde(skip)]
 	pub order: u64,
+	/// Length of the code, kept when the code itself was handed over by a streaming transform.
+	#[serde(skip)]
+	pub code_len: Option<usize>,
+}
+
+/// Makes the sources of `map` relative to `map_dir`, where the map is written. The sources are
+/// relative to the `sourceRoot`, the root directory, or absolute when there is none.
+#[cfg(feature = "fs")]
+fn relocate_source_map(map: &str, module_path: &str, map_dir: &Path) -> Result<Vec<u8>, Error> {
+	let mut map = sourcemap::SourceMap::from_slice(map.as_bytes())?;
+	let source_root = map.get_source_root().map(PathBuf::from);
+	for index in 0..map.get_source_count() {
+		let Some(source) = map.get_source(index) else {
+			continue;
+		};
+		if source.contains("://") {
+			continue;
+		}
+		let source = match &source_root {
+			Some(source_root) => source_root.join(source),
+			None => PathBuf::from(source),
+		};
+		if source.is_absolute() {
+			if let Some(relative) = pathdiff::diff_paths(&source, map_dir) {
+				map.set_source(index, &relative.to_slash_lossy());
+			}
+		}
+	}
+	map.set_source_root(None::<String>);
+	map.set_file(
+		Path::new(module_path)
+			.file_name()
+			.map(|file_name| file_name.to_string_lossy()),
+	);
+	let mut buf = vec![];
+	map.to_writer(&mut buf)?;
+	Ok(buf)
+}
+
+impl TransformModule {
+	/// The file name of the source map of the module, `[file]` in `pattern` is replaced by the
+	/// file name of the module: `[file].map` gives `test.js.map`.
+	pub fn source_map_file(&self, pattern: &str) -> String {
+		let file_name = Path::new(&self.path)
+			.file_name()
+			.map_or(Cow::Borrowed(""), OsStr::to_string_lossy);
+		pattern.replace("[file]", &file_name)
+	}
 }
 
 #[derive(Debug, Clone, Default)]
@@ -204,7 +445,7 @@ impl Emitter for ErrorBuffer {
 	}
 }
 
-pub fn transform_code(config: TransformCodeOptions) -> Result<TransformOutput, anyhow::Error> {
+pub fn transform_code(config: TransformCodeOptions) -> Result<TransformOutput, TransformError> {
 	let source_map = Lrc::new(SourceMap::default());
 	let path_data = parse_path(config.relative_path, config.src_dir)?;
 	let module = parse(
@@ -214,21 +455,26 @@ pub fn transform_code(config: TransformCodeOptions) -> Result<TransformOutput, a
 		Lrc::clone(&source_map),
 	);
 	// dbg!(&module);
-	let transpile_jsx = config.transpile_jsx;
-	let transpile_ts = config.transpile_ts;
 
 	let origin: JsWord = path_data.rel_path.to_slash_lossy().into();
+	let is_vendor = is_vendor_path(&path_data);
+	let input_map = match config.input_map {
+		Some(input_map) if config.source_maps => Some(
+			sourcemap::SourceMap::from_slice(input_map.as_bytes())
+				.map_err(|err| TransformError::invalid_source_map(config.relative_path, err))?,
+		),
+		_ => None,
+	};
+	let source_map_options = SourceMapOptions {
+		root_dir: config.root_dir,
+		input_map: input_map.as_ref(),
+		inline: config.inline_source_maps,
+		sources_content: config.sources_content,
+	};
 
 	match module {
 		Ok((main_module, comments, is_type_script, is_jsx)) => {
-			let extension = match (transpile_ts, transpile_jsx, is_type_script, is_jsx) {
-				(true, true, _, _) => JsWord::from("js"),
-				(true, false, _, true) => JsWord::from("jsx"),
-				(true, false, _, false) => JsWord::from("js"),
-				(false, true, true, _) => JsWord::from("ts"),
-				(false, true, false, _) => JsWord::from("js"),
-				(false, false, _, _) => JsWord::from(path_data.extension.clone()),
-			};
+			let extension = output_extension(&config, &path_data);
 			let error_buffer = ErrorBuffer::default();
 			let handler = swc_common::errors::Handler::with_emitter(
 				true,
@@ -238,101 +484,22 @@ pub fn transform_code(config: TransformCodeOptions) -> Result<TransformOutput, a
 
 			swc_common::GLOBALS.set(&Globals::new(), || {
 				swc_common::errors::HANDLER.set(&handler, || {
-					let unresolved_mark = Mark::new();
-					let top_level_mark = Mark::new();
-
-					let mut main_module = main_module;
-
-					if let Some(strip_exports) = config.strip_exports {
-						let mut visitor = StripExportsVisitor::new(strip_exports);
-						main_module.visit_mut_with(&mut visitor);
-					}
-
-					let mut did_transform = false;
-
-					// Transpile JSX
-					if transpile_ts && is_type_script {
-						did_transform = true;
-						main_module = if is_jsx {
-							main_module.fold_with(&mut typescript::strip_with_jsx(
-								Lrc::clone(&source_map),
-								typescript::Config {
-									pragma: Some("h".to_string()),
-									pragma_frag: Some("Fragment".to_string()),
-									..Default::default()
-								},
-								Some(&comments),
-								top_level_mark,
-							))
-						} else {
-							main_module.fold_with(&mut typescript::strip(top_level_mark))
-						}
-					}
-
-					// Transpile JSX
-					if transpile_jsx && is_jsx {
-						did_transform = true;
-						let mut react_options = react::Options::default();
-						if is_jsx {
-							react_options.next = Some(true);
-							react_options.throw_if_namespace = Some(false);
-							react_options.runtime = Some(react::Runtime::Automatic);
-							react_options.import_source = Some("@builder.io/qwik".to_string());
-						};
-						main_module = main_module.fold_with(&mut react::react(
-							Lrc::clone(&source_map),
-							Some(&comments),
-							react_options,
-							top_level_mark,
-							unresolved_mark,
-						));
-					}
-
-					// Resolve with mark
-					main_module.visit_mut_with(&mut resolver(
+					let QwikModule {
+						module: mut main_module,
+						transform: qwik_transform,
+						did_transform,
 						unresolved_mark,
 						top_level_mark,
-						is_type_script && !transpile_ts,
-					));
-					// Collect import/export metadata
-					let mut collect = global_collect(&main_module);
-
-					transform_props_destructuring(
-						&mut main_module,
-						&mut collect,
-						&config.core_module,
+						server_imports,
+					} = transform_qwik_module(
+						&config,
+						main_module,
+						&path_data,
+						extension.clone(),
+						&comments,
+						&source_map,
 					);
 
-					// Replace const values
-					if let Some(is_server) = config.is_server {
-						if config.mode != EmitMode::Lib {
-							let is_dev = config.mode == EmitMode::Dev;
-							let mut const_replacer =
-								ConstReplacerVisitor::new(is_server, is_dev, &collect);
-							main_module.visit_mut_with(&mut const_replacer);
-						}
-					}
-					let mut qwik_transform = QwikTransform::new(QwikTransformOptions {
-						path_data: &path_data,
-						entry_policy: config.entry_policy,
-						explicit_extensions: config.explicit_extensions,
-						extension: extension.clone(),
-						comments: Some(&comments),
-						global_collect: collect,
-						scope: config.scope,
-						mode: config.mode,
-						core_module: config.core_module,
-						entry_strategy: config.entry_strategy,
-						reg_ctx_name: config.reg_ctx_name,
-						strip_ctx_name: config.strip_ctx_name,
-						strip_event_handlers: config.strip_event_handlers,
-						is_server: config.is_server,
-						cm: Lrc::clone(&source_map),
-					});
-
-					// Run main transform
-					main_module = main_module.fold_with(&mut qwik_transform);
-
 					let mut treeshaker = Treeshaker::new();
 
 					if config.minify != MinifyMode::None {
@@ -375,6 +542,24 @@ pub fn transform_code(config: TransformCodeOptions) -> Result<TransformOutput, a
 							));
 						}
 					}
+					let mut used_server_imports = HashSet::new();
+					collect_used_server_imports(
+						&main_module,
+						&server_imports,
+						&mut used_server_imports,
+					);
+					main_module =
+						downlevel(main_module, config.es_target, unresolved_mark, &comments);
+					if config.minify == MinifyMode::Minify {
+						main_module = minify(
+							main_module,
+							Lrc::clone(&source_map),
+							Some(&comments),
+							unresolved_mark,
+							top_level_mark,
+							config.es_target,
+						);
+					}
 					main_module.visit_mut_with(&mut hygiene_with_config(Default::default()));
 					main_module.visit_mut_with(&mut fixer(None));
 
@@ -397,6 +582,7 @@ pub fn transform_code(config: TransformCodeOptions) -> Result<TransformOutput, a
 						.concat();
 						let need_handle_watch =
 							might_need_handle_watch(&h.data.ctx_kind, &h.data.ctx_name) && is_entry;
+						let hook_analysis = new_hook_analysis(&h, &source_map);
 
 						let (mut hook_module, comments) = new_module(NewModuleCtx {
 							expr: h.expr,
@@ -412,7 +598,8 @@ pub fn transform_code(config: TransformCodeOptions) -> Result<TransformOutput, a
 							is_entry,
 							leading_comments: comments_maps.0.clone(),
 							trailing_comments: comments_maps.1.clone(),
-						})?;
+						})
+						.map_err(|err| TransformError::emit(&hook_path, err))?;
 						if config.minify != MinifyMode::None {
 							hook_module = hook_module.fold_with(&mut simplify::simplifier(
 								unresolved_mark,
@@ -425,17 +612,36 @@ pub fn transform_code(config: TransformCodeOptions) -> Result<TransformOutput, a
 								},
 							));
 						}
+						collect_used_server_imports(
+							&hook_module,
+							&server_imports,
+							&mut used_server_imports,
+						);
+						hook_module =
+							downlevel(hook_module, config.es_target, unresolved_mark, &comments);
+						if config.minify == MinifyMode::Minify {
+							hook_module = minify(
+								hook_module,
+								Lrc::clone(&source_map),
+								Some(&comments),
+								unresolved_mark,
+								top_level_mark,
+								config.es_target,
+							);
+						}
 						hook_module.visit_mut_with(&mut hygiene_with_config(Default::default()));
 						hook_module.visit_mut_with(&mut fixer(None));
+						let imports = collect_relative_imports(&hook_module);
 
 						let (code, map) = emit_source_code(
 							Lrc::clone(&source_map),
 							Some(comments),
 							&hook_module,
-							config.root_dir,
-							config.source_maps,
+							config.source_maps.then_some(&source_map_options),
+							config.minify == MinifyMode::Minify,
+							config.es_target,
 						)
-						.unwrap();
+						.map_err(|err| TransformError::emit(&hook_path, err))?;
 
 						modules.push(TransformModule {
 							code,
@@ -443,32 +649,14 @@ pub fn transform_code(config: TransformCodeOptions) -> Result<TransformOutput, a
 							is_entry,
 							path: hook_path,
 							order: h.hash,
-							hook: Some(HookAnalysis {
-								origin: h.data.origin,
-								name: h.name,
-								entry: h.entry,
-								extension: h.data.extension,
-								canonical_filename: h.canonical_filename,
-								path: h.data.path,
-								parent: h.data.parent_hook,
-								ctx_kind: h.data.ctx_kind,
-								ctx_name: h.data.ctx_name,
-								captures: !h.data.scoped_idents.is_empty(),
-								display_name: h.data.display_name,
-								hash: h.data.hash,
-								loc: (h.span.lo.0, h.span.hi.0),
-							}),
+							orig_path: Some(hook_analysis.origin.clone()),
+							imports: imports.imports,
+							dynamic_imports: imports.dynamic_imports,
+							hook: Some(hook_analysis),
+							code_len: None,
 						});
 					}
 
-					let (code, map) = emit_source_code(
-						Lrc::clone(&source_map),
-						Some(comments),
-						&main_module,
-						config.root_dir,
-						config.source_maps,
-					)?;
-
 					let a = if did_transform && !config.preserve_filenames {
 						[&path_data.file_stem, ".", &extension].concat()
 					} else {
@@ -476,9 +664,20 @@ pub fn transform_code(config: TransformCodeOptions) -> Result<TransformOutput, a
 					};
 					let path = path_data.rel_dir.join(a).to_string_lossy().to_string();
 
-					let mut hasher = DefaultHasher::new();
+					let (code, map) = emit_source_code(
+						Lrc::clone(&source_map),
+						Some(comments),
+						&main_module,
+						config.source_maps.then_some(&source_map_options),
+						config.minify == MinifyMode::Minify,
+						config.es_target,
+					)
+					.map_err(|err| TransformError::emit(&path, err))?;
+
+					let mut hasher = StableHasher::new();
 					hasher.write(path.as_bytes());
 
+					let imports = collect_relative_imports(&main_module);
 					modules.push(TransformModule {
 						is_entry: false,
 						path,
@@ -486,14 +685,20 @@ pub fn transform_code(config: TransformCodeOptions) -> Result<TransformOutput, a
 						map,
 						order: hasher.finish(),
 						hook: None,
+						orig_path: Some(origin.clone()),
+						imports: imports.imports,
+						dynamic_imports: imports.dynamic_imports,
+						code_len: None,
 					});
 
-					let diagnostics = handle_error(&error_buffer, origin, &source_map);
+					report_server_imports(&server_imports, &used_server_imports);
+					let diagnostics = handle_error(&error_buffer, origin, is_vendor, &source_map);
 					Ok(TransformOutput {
 						modules,
 						diagnostics,
 						is_type_script,
 						is_jsx,
+						trace_report: None,
 					})
 				})
 			})
@@ -502,17 +707,384 @@ pub fn transform_code(config: TransformCodeOptions) -> Result<TransformOutput, a
 			let error_buffer = ErrorBuffer::default();
 			let handler = Handler::with_emitter(true, false, Box::new(error_buffer.clone()));
 			err.into_diagnostic(&handler).emit();
-			let diagnostics = handle_error(&error_buffer, origin, &source_map);
+			let diagnostics = handle_error(&error_buffer, origin, is_vendor, &source_map);
 			Ok(TransformOutput {
 				modules: vec![],
 				diagnostics,
 				is_type_script: false,
 				is_jsx: false,
+				trace_report: None,
 			})
 		}
 	}
 }
 
+/// Runs the same analysis as `transform_code`, without generating any code, so it's cheap
+/// enough to run on every change in an editor.
+pub fn analyze_code(config: TransformCodeOptions) -> Result<AnalyzeOutput, TransformError> {
+	let source_map = Lrc::new(SourceMap::default());
+	let path_data = parse_path(config.relative_path, config.src_dir)?;
+	let module = parse(
+		config.code,
+		&path_data,
+		config.root_dir,
+		Lrc::clone(&source_map),
+	);
+
+	let origin: JsWord = path_data.rel_path.to_slash_lossy().into();
+	let is_vendor = is_vendor_path(&path_data);
+	let error_buffer = ErrorBuffer::default();
+	let handler = Handler::with_emitter(true, false, Box::new(error_buffer.clone()));
+
+	let hooks = match module {
+		Ok((main_module, comments, _, _)) => {
+			let extension = output_extension(&config, &path_data);
+			swc_common::GLOBALS.set(&Globals::new(), || {
+				swc_common::errors::HANDLER.set(&handler, || {
+					let QwikModule {
+						module: main_module,
+						transform: qwik_transform,
+						..
+					} = transform_qwik_module(
+						&config,
+						main_module,
+						&path_data,
+						extension,
+						&comments,
+						&source_map,
+					);
+
+					// Hooks are moved out of the main module, their declarations are in both
+					let mut bindings = BindingCollector::default();
+					main_module.visit_with(&mut bindings);
+					for hook in &qwik_transform.hooks {
+						hook.expr.visit_with(&mut bindings);
+					}
+
+					qwik_transform
+						.hooks
+						.iter()
+						.map(|hook| AnalyzedHook {
+							hook: new_hook_analysis(hook, &source_map),
+							captured: hook
+								.data
+								.scoped_idents
+								.iter()
+								.map(|id| CapturedIdent {
+									name: id.0.clone(),
+									loc: bindings
+										.spans
+										.get(id)
+										.map(|span| SourceLocation::from(&source_map, *span)),
+								})
+								.collect(),
+						})
+						.collect()
+				})
+			})
+		}
+		Err(err) => {
+			err.into_diagnostic(&handler).emit();
+			vec![]
+		}
+	};
+
+	let diagnostics = handle_error(&error_buffer, origin.clone(), is_vendor, &source_map);
+	Ok(AnalyzeOutput {
+		modules: vec![ModuleAnalysis {
+			path: origin,
+			hooks,
+		}],
+		diagnostics,
+	})
+}
+
+/// A module after the transforms shared by `transform_code` and `analyze_code`, with the
+/// `QwikTransform` that collected its hooks.
+struct QwikModule<'a> {
+	module: ast::Module,
+	transform: QwikTransform<'a>,
+	did_transform: bool,
+	unresolved_mark: Mark,
+	top_level_mark: Mark,
+	/// The server only imports of a client module.
+	server_imports: HashMap<Id, (JsWord, Span)>,
+}
+
+fn new_hook_analysis(hook: &Hook, source_map: &SourceMap) -> HookAnalysis {
+	HookAnalysis {
+		origin: hook.data.origin.clone(),
+		name: hook.name.clone(),
+		entry: hook.entry.clone(),
+		extension: hook.data.extension.clone(),
+		canonical_filename: hook.canonical_filename.clone(),
+		path: hook.data.path.clone(),
+		parent: hook.data.parent_hook.clone(),
+		ctx_kind: hook.data.ctx_kind,
+		ctx_name: hook.data.ctx_name.clone(),
+		captures: !hook.data.scoped_idents.is_empty(),
+		display_name: hook.data.display_name.clone(),
+		hash: hook.data.hash.clone(),
+		loc: SourceLocation::from(source_map, hook.span),
+	}
+}
+
+fn output_extension(config: &TransformCodeOptions, path_data: &PathData) -> JsWord {
+	let (is_type_script, is_jsx) = parse_filename(path_data);
+	match (
+		config.transpile_ts,
+		config.transpile_jsx,
+		is_type_script,
+		is_jsx,
+	) {
+		(true, true, _, _) => JsWord::from("js"),
+		(true, false, _, true) => JsWord::from("jsx"),
+		(true, false, _, false) => JsWord::from("js"),
+		(false, true, true, _) => JsWord::from("ts"),
+		(false, true, false, _) => JsWord::from("js"),
+		(false, false, _, _) => JsWord::from(path_data.extension.clone()),
+	}
+}
+
+fn is_vendor_path(path_data: &PathData) -> bool {
+	path_data
+		.abs_path
+		.components()
+		.any(|component| component.as_os_str() == "node_modules")
+}
+
+fn transform_qwik_module<'a>(
+	config: &TransformCodeOptions<'a>,
+	mut main_module: ast::Module,
+	path_data: &'a PathData,
+	extension: JsWord,
+	comments: &'a SingleThreadedComments,
+	source_map: &Lrc<SourceMap>,
+) -> QwikModule<'a> {
+	let (is_type_script, is_jsx) = parse_filename(path_data);
+	let unresolved_mark = Mark::new();
+	let top_level_mark = Mark::new();
+
+	// Only the declarations the stripped code was using are removed
+	let used_before = (config.strip_exports.is_some() || config.is_server == Some(false))
+		.then(|| used_top_level_names(&main_module));
+	let mut did_strip = false;
+	if let Some(strip_exports) = config.strip_exports {
+		let mut visitor = StripExportsVisitor::new(strip_exports);
+		main_module.visit_mut_with(&mut visitor);
+		did_strip = visitor.did_strip;
+	}
+	if config.is_server == Some(false) {
+		did_strip |= strip_server_code(&mut main_module, &path_data.file_name);
+	}
+	if let Some(used_before) = used_before.filter(|_| did_strip) {
+		remove_unused_declarations(&mut main_module, &used_before);
+	}
+
+	let mut did_transform = false;
+
+	// Files can switch to another runtime, only the Qwik ones are converted to `_jsxQ` and friends
+	let jsx_import_source = match file_jsx_import_source(&main_module, comments) {
+		Some(source) if source == config.jsx_import_source || source == config.core_module => {
+			Some(source)
+		}
+		Some(_) => None,
+		None => Some(config.jsx_import_source.clone()),
+	};
+
+	// Transpile JSX
+	if config.transpile_ts && is_type_script {
+		did_transform = true;
+		main_module = if is_jsx {
+			main_module.fold_with(&mut typescript::strip_with_jsx(
+				Lrc::clone(source_map),
+				typescript::Config {
+					pragma: Some(config.jsx_pragma.to_string()),
+					pragma_frag: Some(config.jsx_pragma_frag.to_string()),
+					..Default::default()
+				},
+				Some(comments),
+				top_level_mark,
+			))
+		} else {
+			main_module.fold_with(&mut typescript::strip(top_level_mark))
+		}
+	}
+
+	// Transpile JSX
+	if config.transpile_jsx && is_jsx {
+		did_transform = true;
+		let mut react_options = react::Options::default();
+		if is_jsx {
+			react_options.next = Some(true);
+			react_options.throw_if_namespace = Some(false);
+			react_options.runtime = Some(react::Runtime::Automatic);
+			react_options.import_source = Some(config.jsx_import_source.to_string());
+			react_options.pragma = Some(config.jsx_pragma.to_string());
+			react_options.pragma_frag = Some(config.jsx_pragma_frag.to_string());
+		};
+		// The JSX transform reads pragmas before any statement, only the file header counts
+		let later_pragmas = take_later_jsx_pragmas(&main_module, comments);
+		main_module = main_module.fold_with(&mut react::react(
+			Lrc::clone(source_map),
+			Some(comments),
+			react_options,
+			top_level_mark,
+			unresolved_mark,
+		));
+		for (pos, pragmas) in later_pragmas {
+			comments.add_leading_comments(pos, pragmas);
+		}
+	}
+
+	// Resolve with mark
+	main_module.visit_mut_with(&mut resolver(
+		unresolved_mark,
+		top_level_mark,
+		is_type_script && !config.transpile_ts,
+	));
+	// Collect import/export metadata
+	let mut collect = global_collect(&main_module);
+	let server_imports = if config.is_server == Some(false) {
+		server_only_imports(&main_module)
+	} else {
+		HashMap::new()
+	};
+
+	if !config.define.is_empty() {
+		main_module.visit_mut_with(&mut DefineReplacer::new(
+			config.define,
+			&collect,
+			unresolved_mark,
+		));
+	}
+
+	lint_conditional_tasks(&main_module, &collect, &config.core_module);
+
+	transform_props_destructuring(&mut main_module, &mut collect, &config.core_module);
+
+	// Replace const values
+	if let Some(is_server) = config.is_server {
+		if config.mode != EmitMode::Lib {
+			let is_dev = config.mode == EmitMode::Dev;
+			let mut const_replacer =
+				ConstReplacerVisitor::new(is_server, is_dev, &collect, &config.core_module);
+			main_module.visit_mut_with(&mut const_replacer);
+		}
+	}
+	let mut transform = QwikTransform::new(QwikTransformOptions {
+		path_data,
+		entry_policy: config.entry_policy,
+		explicit_extensions: config.explicit_extensions,
+		extension,
+		comments: Some(comments),
+		global_collect: collect,
+		scope: config.scope,
+		mode: config.mode,
+		core_module: config.core_module.clone(),
+		jsx_import_source,
+		jsx_pragma: config.jsx_pragma.clone(),
+		jsx_pragma_frag: config.jsx_pragma_frag.clone(),
+		entry_strategy: config.entry_strategy,
+		reg_ctx_name: config.reg_ctx_name,
+		strip_ctx_name: config.strip_ctx_name,
+		strip_event_handlers: config.strip_event_handlers,
+		is_server: config.is_server,
+		cm: Lrc::clone(source_map),
+	});
+
+	// Run main transform
+	main_module = main_module.fold_with(&mut transform);
+
+	QwikModule {
+		module: main_module,
+		transform,
+		did_transform,
+		unresolved_mark,
+		top_level_mark,
+		server_imports,
+	}
+}
+
+/// Reports the server only imports used by the modules going to the client, once per import.
+fn report_server_imports(
+	server_imports: &HashMap<Id, (JsWord, Span)>,
+	used_server_imports: &HashSet<Id>,
+) {
+	let mut imports: Vec<_> = used_server_imports
+		.iter()
+		.filter_map(|id| server_imports.get(id))
+		.collect();
+	imports.sort_by_key(|(_, span)| span.lo);
+	imports.dedup_by_key(|(_, span)| *span);
+	for (source, span) in imports {
+		HANDLER.with(|handler| {
+			handler
+				.struct_span_err_with_code(
+					*span,
+					&format!("\"{source}\" is server only, its exports throw when called in the browser. Use them inside server$() or a route loader"),
+					errors::get_diagnostic_id(errors::Error::ServerOnlyImport),
+				)
+				.emit();
+		});
+	}
+}
+
+/// Finds the `@jsxImportSource` comment overriding the JSX runtime of a file, it must be in the
+/// comments at the top of the file. Comments before later statements are ignored.
+fn file_jsx_import_source(
+	module: &ast::Module,
+	comments: &SingleThreadedComments,
+) -> Option<JsWord> {
+	let positions =
+		std::iter::once(module.span.lo).chain(module.body.first().map(|item| item.span().lo));
+	for pos in positions {
+		let source = comments.with_leading(pos, |comments| {
+			comments
+				.iter()
+				.filter(|comment| comment.kind == CommentKind::Block)
+				.flat_map(|comment| comment.text.lines())
+				.find_map(|line| {
+					let mut words = line.trim().trim_start_matches('*').split_whitespace();
+					while let Some(pragma) = words.next() {
+						if pragma == "@jsxImportSource" {
+							return words.next().map(JsWord::from);
+						}
+					}
+					None
+				})
+		});
+		if source.is_some() {
+			return source;
+		}
+	}
+	None
+}
+
+/// Takes out the `@jsx` pragma comments before the statements after the file header, they are
+/// added back once the JSX is transformed.
+fn take_later_jsx_pragmas(
+	module: &ast::Module,
+	comments: &SingleThreadedComments,
+) -> Vec<(BytePos, Vec<Comment>)> {
+	let header = module.span.lo;
+	module
+		.body
+		.iter()
+		.skip(1)
+		.map(|item| item.span().lo)
+		.filter(|pos| *pos != header)
+		.filter(|pos| {
+			comments.with_leading(*pos, |comments| {
+				comments.iter().any(|comment| {
+					comment.kind == CommentKind::Block && comment.text.contains("@jsx")
+				})
+			})
+		})
+		.filter_map(|pos| Some((pos, comments.take_leading(pos)?)))
+		.collect()
+}
+
 fn parse(
 	code: &str,
 	path_data: &PathData,
@@ -556,7 +1128,7 @@ fn parse(
 	}
 }
 
-fn parse_filename(path_data: &PathData) -> (bool, bool) {
+pub fn parse_filename(path_data: &PathData) -> (bool, bool) {
 	match path_data.extension.as_str() {
 		"ts" => (true, false),
 		"mts" => (true, false),
@@ -571,12 +1143,37 @@ fn parse_filename(path_data: &PathData) -> (bool, bool) {
 	}
 }
 
+/// How the source map of an emitted module is built.
+pub struct SourceMapOptions<'a> {
+	pub root_dir: Option<&'a Path>,
+	/// The map of the input code, the emitted map is composed with it.
+	pub input_map: Option<&'a sourcemap::SourceMap>,
+	/// Appends the map to the code as a data URL comment instead of returning it.
+	pub inline: bool,
+	pub sources_content: bool,
+}
+
+struct SourceMapConfig {
+	sources_content: bool,
+}
+
+impl SourceMapGenConfig for SourceMapConfig {
+	fn file_name_to_source(&self, f: &FileName) -> String {
+		f.to_string()
+	}
+
+	fn inline_sources_content(&self, _f: &FileName) -> bool {
+		self.sources_content
+	}
+}
+
 pub fn emit_source_code(
 	source_map: Lrc<SourceMap>,
 	comments: Option<SingleThreadedComments>,
 	program: &ast::Module,
-	root_dir: Option<&Path>,
-	source_maps: bool,
+	source_maps: Option<&SourceMapOptions>,
+	minify: bool,
+	target: ast::EsVersion,
 ) -> Result<(String, Option<String>), Error> {
 	let mut src_map_buf = Vec::new();
 	let mut buf = Vec::new();
@@ -585,15 +1182,15 @@ pub fn emit_source_code(
 			Lrc::clone(&source_map),
 			"\n",
 			&mut buf,
-			if source_maps {
+			if source_maps.is_some() {
 				Some(&mut src_map_buf)
 			} else {
 				None
 			},
 		));
 		let config = swc_ecmascript::codegen::Config {
-			minify: false,
-			target: ast::EsVersion::latest(),
+			minify,
+			target,
 			ascii_only: false,
 			omit_last_semi: false,
 		};
@@ -606,29 +1203,76 @@ pub fn emit_source_code(
 		emitter.emit_module(program)?;
 	}
 
+	let mut code = unsafe { str::from_utf8_unchecked(&buf).to_string() };
+	let Some(options) = source_maps else {
+		return Ok((code, None));
+	};
+	let mut s = source_map.build_source_map_with_config(
+		&src_map_buf,
+		None,
+		SourceMapConfig {
+			sources_content: options.sources_content,
+		},
+	);
+	if let Some(input_map) = options.input_map {
+		s = compose_source_maps(&s, input_map, options.sources_content);
+	}
+	if let Some(root_dir) = options.root_dir {
+		s.set_source_root(Some(root_dir.to_str().unwrap()));
+	}
 	let mut map_buf = vec![];
-	let emit_source_maps = if source_maps {
-		let mut s = source_map.build_source_map(&src_map_buf);
-		if let Some(root_dir) = root_dir {
-			s.set_source_root(Some(root_dir.to_str().unwrap()));
+	if s.to_writer(&mut map_buf).is_err() {
+		return Ok((code, None));
+	}
+	if options.inline {
+		code.push_str("\n//# sourceMappingURL=data:application/json;charset=utf-8;base64,");
+		code.push_str(&base64::engine::general_purpose::STANDARD.encode(&map_buf));
+		return Ok((code, None));
+	}
+	Ok((
+		code,
+		Some(unsafe { str::from_utf8_unchecked(&map_buf).to_string() }),
+	))
+}
+
+/// Maps the tokens of `map`, which point into the generated input code, to the original sources
+/// of `input_map`. Tokens the input map knows nothing about are dropped.
+fn compose_source_maps(
+	map: &sourcemap::SourceMap,
+	input_map: &sourcemap::SourceMap,
+	sources_content: bool,
+) -> sourcemap::SourceMap {
+	let mut builder = sourcemap::SourceMapBuilder::new(map.get_file());
+	for token in map.tokens() {
+		let Some(original) = input_map.lookup_token(token.get_src_line(), token.get_src_col()) else {
+			continue;
+		};
+		// The lookup falls back to the last token before the position, even on previous lines
+		if !original.has_source() || original.get_dst_line() != token.get_src_line() {
+			continue;
+		}
+		let raw = builder.add(
+			token.get_dst_line(),
+			token.get_dst_col(),
+			original.get_src_line(),
+			original.get_src_col(),
+			original.get_source(),
+			original.get_name().or_else(|| token.get_name()),
+		);
+		if sources_content && builder.get_source_contents(raw.src_id).is_none() {
+			builder.set_source_contents(
+				raw.src_id,
+				input_map.get_source_contents(original.get_src_id()),
+			);
 		}
-		s.to_writer(&mut map_buf).is_ok()
-	} else {
-		false
-	};
-	if emit_source_maps {
-		Ok((
-			unsafe { str::from_utf8_unchecked(&buf).to_string() },
-			unsafe { Some(str::from_utf8_unchecked(&map_buf).to_string()) },
-		))
-	} else {
-		Ok((unsafe { str::from_utf8_unchecked(&buf).to_string() }, None))
 	}
+	builder.into_sourcemap()
 }
 
 fn handle_error(
 	error_buffer: &ErrorBuffer,
 	origin: JsWord,
+	is_vendor: bool,
 	source_map: &Lrc<SourceMap>,
 ) -> Vec<Diagnostic> {
 	error_buffer
@@ -648,6 +1292,13 @@ fn handle_error(
 				}
 			});
 
+			let category = match diagnostic.level {
+				Level::Warning | Level::Note | Level::Help => DiagnosticCategory::Warning,
+				// Code in node_modules can not be fixed by the user
+				_ if is_vendor => DiagnosticCategory::SourceError,
+				_ => DiagnosticCategory::Error,
+			};
+
 			let span = diagnostic.span.clone();
 			let suggestions = diagnostic.suggestions.clone();
 
@@ -669,6 +1320,33 @@ fn handle_error(
 				)
 			};
 
+			let fixes = if suggestions.is_empty() {
+				None
+			} else {
+				Some(
+					suggestions
+						.iter()
+						.flat_map(|suggestion| {
+							suggestion
+								.substitutions
+								.iter()
+								.map(|substitution| DiagnosticFix {
+									message: suggestion.msg.clone(),
+									applicability: suggestion.applicability.into(),
+									edits: substitution
+										.parts
+										.iter()
+										.map(|part| DiagnosticEdit {
+											loc: SourceLocation::from(source_map, part.span),
+											replacement: part.snippet.clone(),
+										})
+										.collect(),
+								})
+						})
+						.collect(),
+				)
+			};
+
 			let suggestions = if suggestions.is_empty() {
 				None
 			} else {
@@ -686,7 +1364,8 @@ fn handle_error(
 				message,
 				highlights,
 				suggestions,
-				category: DiagnosticCategory::Error,
+				fixes,
+				category,
 				scope: DiagnosticScope::Optimizer,
 			}
 		})
@@ -705,27 +1384,37 @@ pub struct PathData {
 	pub file_prefix: String,
 }
 
-pub fn parse_path(src: &str, base_dir: &Path) -> Result<PathData, Error> {
+pub fn parse_path(src: &str, base_dir: &Path) -> Result<PathData, TransformError> {
 	let path = Path::new(src);
 	let file_stem = path
 		.file_stem()
 		.and_then(OsStr::to_str)
 		.map(Into::into)
-		.with_context(|| format!("Computing file stem for {}", path.to_string_lossy()))?;
-
-	let rel_dir = path.parent().unwrap().to_path_buf();
-	let extension = path.extension().and_then(OsStr::to_str).unwrap();
+		.ok_or_else(|| TransformError::invalid_path(path, "has no file stem"))?;
+
+	let rel_dir = path
+		.parent()
+		.ok_or_else(|| TransformError::invalid_path(path, "has no parent directory"))?
+		.to_path_buf();
+	let extension = path
+		.extension()
+		.and_then(OsStr::to_str)
+		.ok_or_else(|| TransformError::invalid_path(path, "has no extension"))?;
 	let file_name = path
 		.file_name()
 		.and_then(OsStr::to_str)
-		.with_context(|| format!("Computing filename for {}", path.to_string_lossy()))?;
+		.ok_or_else(|| TransformError::invalid_path(path, "has no file name"))?;
 	let file_prefix = file_name
 		.rsplitn(2, '.')
 		.last()
-		.with_context(|| format!("Computing file_prefix for {}", path.to_string_lossy()))?;
+		.ok_or_else(|| TransformError::invalid_path(path, "has no file prefix"))?;
 
 	let abs_path = normalize_path(base_dir.join(path));
-	let abs_dir = normalize_path(abs_path.parent().unwrap());
+	let abs_dir = normalize_path(
+		abs_path
+			.parent()
+			.ok_or_else(|| TransformError::invalid_path(&abs_path, "has no parent directory"))?,
+	);
 
 	Ok(PathData {
 		abs_path,
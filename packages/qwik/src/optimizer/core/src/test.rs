@@ -1,6 +1,7 @@
 #![allow(unused_must_use)]
 
 use super::*;
+use crate::utils::DiagnosticCategory;
 use serde_json::to_string_pretty;
 
 macro_rules! test_input {
@@ -24,14 +25,25 @@ macro_rules! test_input {
 			input: vec![TransformModuleInput {
 				code: input.code.clone(),
 				path: input.filename,
+				map: None,
 			}],
 			source_maps: true,
+			inline_source_maps: false,
+			sources_content: false,
 			minify: input.minify,
 			transpile_ts: input.transpile_ts,
 			transpile_jsx: input.transpile_jsx,
 			preserve_filenames: input.preserve_filenames,
 			explicit_extensions: input.explicit_extensions,
 			manual_chunks: input.manual_chunks,
+			sized_chunks: input.sized_chunks,
+			symbol_traces: input.symbol_traces,
+			entry_rules: input.entry_rules,
+			es_target: input.es_target,
+			jsx_import_source: input.jsx_import_source,
+			jsx_pragma: None,
+			jsx_pragma_frag: None,
+			define: input.define,
 			entry_strategy: input.entry_strategy,
 			mode: input.mode,
 			scope: input.scope,
@@ -957,6 +969,173 @@ export const App = component$(() => {
 	});
 }
 
+#[test]
+fn example_fix_capturing_fn() {
+	test_input!(TestInput {
+		code: r#"
+import { $, component$ } from '@builder.io/qwik';
+
+export const App = component$(() => {
+    async function load(id: string): Promise<string> {
+      return id;
+    }
+
+    return $(() => {
+      load('a');
+      return (
+          <div></div>
+      )
+    });
+})
+"#
+		.to_string(),
+		transpile_ts: true,
+		transpile_jsx: true,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_fix_capturing_fn_applied() {
+	// The code of `example_fix_capturing_fn` once its fix is applied
+	test_input!(TestInput {
+		code: r#"
+import { $, component$ } from '@builder.io/qwik';
+
+export const App = component$(() => {
+    const load = $(async (id: string): Promise<string> => {
+      return id;
+    });
+
+    return $(() => {
+      load('a');
+      return (
+          <div></div>
+      )
+    });
+})
+"#
+		.to_string(),
+		transpile_ts: true,
+		transpile_jsx: true,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_lint_capture_mutable_object() {
+	test_input!(TestInput {
+		code: r#"
+import { $, component$ } from '@builder.io/qwik';
+
+export const App = component$(() => {
+    let state = { count: 0 };
+    const items = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33];
+    const small = { a: 1 };
+
+    return $(() => {
+      state.count++;
+      return (
+          <div>{items.length}{small.a}</div>
+      )
+    });
+})
+"#
+		.to_string(),
+		transpile_ts: true,
+		transpile_jsx: true,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_lint_capture_non_serializable() {
+	test_input!(TestInput {
+		code: r#"
+import { $, component$ } from '@builder.io/qwik';
+
+class Store {}
+
+export const App = component$(() => {
+    const format = (value) => `${value}!`;
+    const store = new Store();
+    const date = new Date();
+
+    return $(() => {
+      return (
+          <div>{format(store)}{date.getTime()}</div>
+      )
+    });
+})
+"#
+		.to_string(),
+		transpile_ts: true,
+		transpile_jsx: true,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_lint_conditional_task() {
+	test_input!(TestInput {
+		code: r#"
+import { component$, useTask$, useVisibleTask$, useSignal } from '@builder.io/qwik';
+
+export const App = component$((props) => {
+    const count = useSignal(0);
+    useTask$(() => {
+      if (props.enabled) {
+        count.value++;
+      }
+    });
+    if (props.enabled) {
+      useTask$(() => {
+        count.value++;
+      });
+    }
+    props.visible && useVisibleTask$(() => {
+      count.value++;
+    });
+    if (!props.ready) {
+      return <div>Loading</div>;
+    }
+    useTask$(() => {
+      count.value++;
+    });
+    return <div>{count.value}</div>;
+})
+"#
+		.to_string(),
+		transpile_ts: true,
+		transpile_jsx: true,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn vendor_errors_are_source_errors() {
+	let transform = |src_dir: &str| {
+		transform_modules(TransformModulesOptions {
+			src_dir: src_dir.into(),
+			input: vec![TransformModuleInput {
+				code: "export const a = ;".into(),
+				path: "index.js".into(),
+				map: None,
+			}],
+			..TransformModulesOptions::default()
+		})
+		.unwrap()
+	};
+
+	let res = transform("/user/qwik/src/");
+	assert_eq!(res.diagnostics.len(), 1);
+	assert_eq!(res.diagnostics[0].category, DiagnosticCategory::Error);
+
+	let res = transform("/user/qwik/node_modules/lib/");
+	assert_eq!(res.diagnostics.len(), 1);
+	assert_eq!(res.diagnostics[0].category, DiagnosticCategory::SourceError);
+}
+
 #[test]
 fn example_renamed_exports() {
 	test_input!(TestInput {
@@ -1651,426 +1830,385 @@ export const Child = component$(() => {
 }
 
 #[test]
-fn example_strip_exports_unused() {
+fn example_sized_chunks() {
 	test_input!(TestInput {
 		code: r#"
-import { component$ } from '@builder.io/qwik';
-import mongodb from 'mongodb';
+import { component$, useStore } from '@builder.io/qwik';
 
-export const onGet = () => {
-    const data = mongodb.collection.whatever;
-    return {
-        body: {
-        data
-        }
-    }
-};
+export const Counter = component$(() => {
+    const state = useStore({ count: 0 });
+    return (
+        <div>
+            <button onClick$={() => state.count++}>+</button>
+            <button onClick$={() => state.count--}>-</button>
+            <span onMouseOver$={() => console.log('hover', state.count)}>{state.count}</span>
+        </div>
+    );
+});
 
-export default component$(()=> {
-    return <div>cmp</div>
+export const Footer = component$(() => {
+    return (
+        <footer onClick$={() => console.log('footer')}>Footer</footer>
+    );
 });
 "#
 		.to_string(),
-		strip_exports: Some(vec!["onGet".into()]),
+		transpile_ts: true,
+		transpile_jsx: true,
+		entry_strategy: EntryStrategy::Sized,
+		sized_chunks: Some(SizedChunks {
+			min_size: Some(300),
+			max_size: Some(700),
+			usage: Some(HashMap::from_iter(vec![("t81gj5Z0vOM".into(), 10.0)])),
+		}),
+		mode: EmitMode::Prod,
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_strip_exports_used() {
+fn example_entry_rules() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, useResource$ } from '@builder.io/qwik';
-import mongodb from 'mongodb';
-
-export const onGet = () => {
-    const data = mongodb.collection.whatever;
-    return {
-        body: {
-        data
-        }
-    }
-};
+import { component$, useStore } from '@builder.io/qwik';
 
-export default component$(()=> {
-    useResource$(() => {
-        return onGet();
-    })
-    return <div>cmp</div>
+export const Counter = component$(() => {
+    const state = useStore({ count: 0 });
+    return (
+        <div>
+            <button onClick$={() => state.count++}>+</button>
+            <span onMouseOver$={() => console.log('hover')}>{state.count}</span>
+        </div>
+    );
 });
 "#
 		.to_string(),
-		strip_exports: Some(vec!["onGet".into()]),
+		transpile_ts: true,
+		transpile_jsx: true,
+		entry_strategy: EntryStrategy::Single,
+		entry_rules: Some(vec![
+			EntryRule {
+				ctx_name: Some(vec!["component$".into()]),
+				..EntryRule::default()
+			},
+			EntryRule {
+				ctx_kind: Some(HookKind::EventHandler),
+				origin: Some("**/*.tsx".into()),
+				captures: Some(false),
+				entry: Some("entry_static_events".into()),
+				..EntryRule::default()
+			},
+		]),
+		mode: EmitMode::Prod,
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_strip_server_code() {
+fn example_minify() {
 	test_input!(TestInput {
-        code: r#"
-import { component$, serverLoader$, serverStuff$, $, client$, useStore, useTask$ } from '@builder.io/qwik';
-import { isServer } from '@builder.io/qwik/build';
-import mongo from 'mongodb';
-import redis from 'redis';
-import { handler } from 'serverless';
+		code: r#"
+import { component$, useStore } from '@builder.io/qwik';
 
-export const Parent = component$(() => {
-    const state = useStore({
-        text: ''
-    });
+export const Counter = component$((props: { step: number }) => {
+    const state = useStore({ count: 0 });
+    const increment = (amount: number) => {
+        const next = state.count + amount;
+        state.count = next;
+    };
+    return (
+        <div>
+            <button onClick$={() => increment(props.step)}>+</button>
+            <span>{state.count}</span>
+        </div>
+    );
+});
+"#
+		.to_string(),
+		transpile_ts: true,
+		transpile_jsx: true,
+		minify: MinifyMode::Minify,
+		entry_strategy: EntryStrategy::Hook,
+		mode: EmitMode::Prod,
+		..TestInput::default()
+	});
+}
 
-    // Double count watch
-    useTask$(async () => {
-        if (!isServer) return;
-        state.text = await mongo.users();
-        redis.set(state.text);
-    });
+#[test]
+fn example_define() {
+	test_input!(TestInput {
+		code: r#"
+import { component$, useTask$ } from '@builder.io/qwik';
+import { beta } from '@acme/flags';
 
-    serverStuff$(async () => {
-        // should be removed too
-        const a = $(() => {
-            // from $(), should not be removed
-        });
-        const b = client$(() => {
-            // from clien$(), should not be removed
+export const App = component$(() => {
+    if (__ANALYTICS__) {
+        useTask$(() => {
+            console.log('tracking', { env: process.env.NODE_ENV });
         });
-        return [a,b];
-    })
-
-    serverLoader$(handler);
-
-    useTask$(() => {
-        // Code
-    });
-
+    }
     return (
-        <div onClick$={() => console.log('parent')}>
-            {state.text}
+        <div title={process.env.NODE_ENV}>
+            {beta ? <button onClick$={() => console.log('beta')}>Try</button> : <span>{API}</span>}
         </div>
     );
 });
 "#
-        .to_string(),
-        transpile_ts: true,
-        transpile_jsx: true,
-        entry_strategy: EntryStrategy::Hook,
-        strip_ctx_name: Some(vec!["server".into()]),
-        ..TestInput::default()
-    });
+		.to_string(),
+		transpile_ts: true,
+		transpile_jsx: true,
+		define: Some(HashMap::from([
+			("__ANALYTICS__".into(), serde_json::json!(false)),
+			(
+				"process.env.NODE_ENV".into(),
+				serde_json::json!("production")
+			),
+			("@acme/flags:beta".into(), serde_json::json!(false)),
+			(
+				"API".into(),
+				serde_json::json!({ "url": "/api", "retries": -1 })
+			),
+		])),
+		..TestInput::default()
+	});
 }
 
 #[test]
-fn example_server_auth() {
+fn example_define_assignment_targets() {
 	test_input!(TestInput {
 		code: r#"
-import GitHub from '@auth/core/providers/github'
-import Facebook from 'next-auth/providers/facebook'
-import Google from 'next-auth/providers/google'
-import {serverAuth$, auth$} from '@auth/qwik';
+import { component$ } from '@builder.io/qwik';
 
-export const { onRequest, logout, getSession, signup } = serverAuth$({
-    providers: [
-    GitHub({
-        clientId: process.env.GITHUB_ID,
-        clientSecret: process.env.GITHUB_SECRET
-    }),
-    Facebook({
-        clientId: import.meta.env.FACEBOOK_ID,
-        clientSecret: import.meta.env.FACEBOOK_SECRET
-    }),
-    Google({
-        clientId: process.env.GOOGLE_ID,
-        clientSecret: process.env.GOOGLE_SECRET
-    })
-    ]
-});
-
-export const { onRequest, logout, getSession, signup } = auth$({
-    providers: [
-    GitHub({
-        clientId: process.env.GITHUB_ID,
-        clientSecret: process.env.GITHUB_SECRET
-    }),
-    Facebook({
-        clientId: process.env.FACEBOOK_ID,
-        clientSecret: process.env.FACEBOOK_SECRET
-    }),
-    Google({
-        clientId: process.env.GOOGLE_ID,
-        clientSecret: process.env.GOOGLE_SECRET
-    })
-    ]
+export const App = component$(() => {
+    __COUNT__++;
+    --__COUNT__;
+    delete process.env.NODE_ENV;
+    [__COUNT__] = [1];
+    ({ mode: process.env.NODE_ENV } = { mode: 'dev' });
+    return <div title={process.env.NODE_ENV}>{__COUNT__}</div>;
 });
 "#
 		.to_string(),
 		transpile_ts: true,
 		transpile_jsx: true,
-		entry_strategy: EntryStrategy::Hook,
+		define: Some(HashMap::from([
+			("__COUNT__".into(), serde_json::json!(0)),
+			(
+				"process.env.NODE_ENV".into(),
+				serde_json::json!("production")
+			),
+		])),
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_strip_client_code() {
+fn example_define_minify_none() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, useClientMount$, useStore, useTask$ } from '@builder.io/qwik';
-import mongo from 'mongodb';
-import redis from 'redis';
-import threejs from 'threejs';
-import { a } from './keep';
-import { b } from '../keep2';
-import { c } from '../../remove';
-
-export const Parent = component$(() => {
-    const state = useStore({
-        text: ''
-    });
-
-    // Double count watch
-    useClientMount$(async () => {
-        state.text = await mongo.users();
-        redis.set(state.text, a, b, c);
-    });
-
-    useTask$(() => {
-        // Code
-    });
+import { component$, useTask$ } from '@builder.io/qwik';
 
-    return (
-        <div
-            shouldRemove$={() => state.text}
-            onClick$={() => console.log('parent', state, threejs)}
-        >
-            <Div
-                onClick$={() => console.log('keep')}
-                render$={() => state.text}
-            />
-            {state.text}
-        </div>
-    );
+export const App = component$(() => {
+    const counts = { production: 0 };
+    counts[process.env.NODE_ENV]++;
+    if (1 + 1 === 2 && !counts.length) {
+        counts.total = void 0;
+    }
+    if (process.env.NODE_ENV === 'production') {
+        counts.prod = true;
+    } else {
+        useTask$(() => console.log('dev'));
+    }
+    return <div>{__DEBUG__ && <pre>{JSON.stringify(counts)}</pre>}</div>;
 });
 "#
 		.to_string(),
-		filename: "components/component.tsx".into(),
 		transpile_ts: true,
 		transpile_jsx: true,
-		entry_strategy: EntryStrategy::Inline,
-		strip_ctx_name: Some(vec!["useClientMount$".into(),]),
-		strip_event_handlers: true,
+		minify: MinifyMode::None,
+		define: Some(HashMap::from([
+			(
+				"process.env.NODE_ENV".into(),
+				serde_json::json!("production")
+			),
+			("__DEBUG__".into(), serde_json::json!(false)),
+		])),
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn issue_150() {
+fn example_es_target() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, $ } from '@builder.io/qwik';
-import { hola } from 'sdfds';
+import { component$, useStore } from '@builder.io/qwik';
 
-export const Greeter = component$(() => {
-    const stuff = useStore();
-    return $(() => {
-        return (
-            <div
-                class={{
-                    'foo': true,
-                    'bar': stuff.condition,
-                    'baz': hola ? 'true' : 'false',
-                }}
-            />
-        )
-    });
-});
+class Store {
+    count = 1_000;
+    static instances = 0;
+    static {
+        this.instances++;
+    }
+}
 
-const d = $(()=>console.log('thing'));
+export const Counter = component$((props: { user?: { name?: string } }) => {
+    const state = useStore({ store: new Store(), label: null });
+    return (
+        <div onClick$={() => {
+            state.label ??= props.user?.name;
+            state.store.count ||= 1;
+            try {
+                props.user?.name?.toString();
+            } catch {
+                state.label = null;
+            }
+        }}>
+            {props.user?.name ?? 'anonymous'}
+        </div>
+    );
+});
 "#
 		.to_string(),
 		transpile_ts: true,
 		transpile_jsx: true,
+		es_target: Some(EsTarget::Es2018),
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_input_bind() {
+fn example_es_target_classes() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, $ } from '@builder.io/qwik';
+import { component$ } from '@builder.io/qwik';
 
-export const Greeter = component$(() => {
-    const value = useSignal(0);
-    const checked = useSignal(false);
-    const stuff = useSignal();
-    return (
-        <>
-            <input bind:value={value} />
-            <input bind:checked={checked} />
-            <input bind:stuff={stuff} />
-            <div>{value}</div>
-            <div>{value.value}</div>
-        </>
+const y = 1;
+let n = 0;
+const key = () => 'k' + n++;
 
-    )
+class A {
+    x = y;
+    [key()] = 2;
+    constructor(y) {
+        this.z = y;
+    }
+}
+
+class B extends A {
+    #count = 0;
+    #inc() {
+        return ++this.#count;
+    }
+    constructor(y) {
+        if (y) {
+            super(y);
+        } else {
+            super(0);
+        }
+    }
+    static has(b) {
+        return #count in b;
+    }
+}
+
+export const Counter = component$(() => {
+    const b = new B(1);
+    return <div onClick$={() => console.log(B.has(b), new A(2))}></div>;
 });
 "#
 		.to_string(),
-		entry_strategy: EntryStrategy::Inline,
 		transpile_ts: true,
 		transpile_jsx: true,
-		mode: EmitMode::Prod,
+		es_target: Some(EsTarget::Es2018),
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_import_assertion() {
+fn example_es_target_es5() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, $ } from '@builder.io/qwik';
-import json from "./foo.json" assert { type: "json" };
+import { component$, useSignal } from '@builder.io/qwik';
 
-export const Greeter = component$(() => {
-    return json;
+class Store {
+    constructor(base) {
+        this.base = base;
+    }
+    async load(key) {
+        const { value = 0, ...rest } = await fetch(`/api/${key}`).then((res) => res.json());
+        return [value ** this.base, rest];
+    }
+}
+
+export const Counter = component$(() => {
+    const count = useSignal(0);
+    return <div onClick$={async () => {
+        const [value] = await new Store(2).load('count');
+        count.value = value;
+    }}>{count.value}</div>;
 });
 "#
 		.to_string(),
 		transpile_ts: true,
 		transpile_jsx: true,
+		es_target: Some(EsTarget::Es5),
 		..TestInput::default()
 	});
 }
 
-#[cfg(target_os = "windows")]
-#[test]
-fn issue_188() {
-	let res = test_input!({
-		filename: r"components\apps\apps.tsx".to_string(),
-		src_dir: r"C:\users\apps".to_string(),
-		code: r#"
-import { component$, $ } from '@builder.io/qwik';
-
-export const Greeter = component$(() => {
-    return $(() => {
-        return (
-            <div/>
-        )
-    });
-});
-
-const d = $(()=>console.log('thing'));
-"#
-		.to_string(),
-		transpile_ts: true,
-		transpile_jsx: true,
-		snapshot: false,
-	})
-	.unwrap();
-	let last_module = res.modules.last().unwrap();
-	assert_eq!(last_module.path, r"C:/users/apps/components/apps/apps.tsx")
-}
 #[test]
-fn issue_476() {
+fn example_jsx_import_source_option() {
 	test_input!(TestInput {
 		code: r#"
-import { Counter } from "./counter.tsx";
+import { component$, useSignal } from '@acme/qwik';
 
-export const Root = () => {
+export const Counter = component$(() => {
+    const count = useSignal(0);
     return (
-        <html>
-            <head>
-                <meta charset="utf-8" />
-                <title>Qwik Blank App</title>
-            </head>
-            <body>
-                <Counter initial={3} />
-            </body>
-        </html>
+        <>
+            <button class="counter" onClick$={() => count.value++}>+</button>
+            <span>{count.value}</span>
+        </>
     );
-};
+});
 "#
 		.to_string(),
-		transpile_ts: false,
-		transpile_jsx: false,
+		transpile_ts: true,
+		transpile_jsx: true,
+		jsx_import_source: Some("@acme/qwik".into()),
+		core_module: Some("@acme/qwik".into()),
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn issue_964() {
+fn example_jsx_import_source_comment() {
 	test_input!(TestInput {
 		code: r#"
+/** @jsxImportSource @builder.io/qwik */
 import { component$ } from '@builder.io/qwik';
 
 export const App = component$(() => {
-    console.log(function*(lo: any, t: any) {
-    console.log(yield (yield lo)(t.href).then((r) => r.json()));
-    });
-
-    return <p>Hello Qwik</p>;
+    return <div class="app">Hello</div>;
 });
 "#
 		.to_string(),
 		transpile_ts: true,
 		transpile_jsx: true,
+		jsx_import_source: Some("@acme/qwik".into()),
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_immutable_analysis() {
+fn example_jsx_import_source_comment_not_header() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, useStore, $ } from '@builder.io/qwik';
-import importedValue from 'v';
-import styles from './styles.module.css';
+import { component$ } from '@builder.io/qwik';
 
-export const App = component$((props) => {
-    const {Model} = props;
-    const state = useStore({count: 0});
-    const remove = $((id: number) => {
-        const d = state.data;
-        d.splice(
-          d.findIndex((d) => d.id === id),
-          1
-        )
-      });
-    return (
-        <>
-            <p class="stuff" onClick$={props.onClick$}>Hello Qwik</p>
-            <Div
-                class={styles.foo}
-                document={window.document}
-                onClick$={props.onClick$}
-                onEvent$={() => console.log('stuff')}
-                transparent$={() => {console.log('stuff')}}
-                immutable1="stuff"
-                immutable2={{
-                    foo: 'bar',
-                    baz: importedValue ? true : false,
-                }}
-                immutable3={2}
-                immutable4$={(ev) => console.log(state.count)}
-                immutable5={[1, 2, importedValue, null, {}]}
-            >
-                <p>Hello Qwik</p>
-            </Div>
-            [].map(() => (
-                <Model
-                    class={state}
-                    remove$={remove}
-                    mutable1={{
-                        foo: 'bar',
-                        baz: state.count ? true : false,
-                    }}
-                    mutable2={(() => console.log(state.count))()}
-                    mutable3={[1, 2, state, null, {}]}
-                />
-            ));
-        </>
-    );
+/** @jsxImportSource react */
+export const App = component$(() => {
+    return <div class="app">Hello</div>;
 });
 "#
 		.to_string(),
@@ -2081,918 +2219,822 @@ export const App = component$((props) => {
 }
 
 #[test]
-fn example_ts_enums_issue_1341() {
+fn example_jsx_pragma_aliased_import() {
 	test_input!(TestInput {
+		filename: "test.jsx".into(),
 		code: r#"
-import { component$, useStore } from '@builder.io/qwik';
-
-enum Thing {
-    A,
-    B
-}
+import { component$, h as jsx, Fragment as Frag } from '@builder.io/qwik';
+import { h } from 'preact';
 
 export const App = component$(() => {
-    console.log(Thing.A);
-    return (
-        <>
-            <p class="stuff">Hello Qwik</p>
-        </>
-    );
+    return <><div onClick$={() => h('div')}>Hello</div></>;
 });
 "#
 		.to_string(),
-		transpile_ts: true,
-		transpile_jsx: true,
+		transpile_jsx: false,
+		minify: MinifyMode::None,
+		entry_strategy: EntryStrategy::Hook,
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_ts_enums_no_transpile() {
+fn example_strip_exports_unused() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, useStore } from '@builder.io/qwik';
+import { component$ } from '@builder.io/qwik';
+import mongodb from 'mongodb';
 
-export enum Thing {
-    A,
-    B
-}
+export const onGet = () => {
+    const data = mongodb.collection.whatever;
+    return {
+        body: {
+        data
+        }
+    }
+};
 
-export const App = component$(() => {
-    console.log(Thing.A);
-    return (
-        <>
-            <p class="stuff">Hello Qwik</p>
-        </>
-    );
+export default component$(()=> {
+    return <div>cmp</div>
 });
 "#
 		.to_string(),
-		transpile_ts: false,
-		transpile_jsx: false,
-
+		strip_exports: Some(vec!["onGet".into()]),
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_ts_enums() {
+fn example_strip_exports_used() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, useStore } from '@builder.io/qwik';
+import { component$, useResource$ } from '@builder.io/qwik';
+import mongodb from 'mongodb';
 
-export enum Thing {
-    A,
-    B
-}
+export const onGet = () => {
+    const data = mongodb.collection.whatever;
+    return {
+        body: {
+        data
+        }
+    }
+};
 
-export const App = component$(() => {
-    console.log(Thing.A);
-    return (
-        <>
-            <p class="stuff">Hello Qwik</p>
-        </>
-    );
+export default component$(()=> {
+    useResource$(() => {
+        return onGet();
+    })
+    return <div>cmp</div>
 });
 "#
 		.to_string(),
-		transpile_ts: true,
-		transpile_jsx: true,
+		strip_exports: Some(vec!["onGet".into()]),
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn special_jsx() {
+fn example_strip_exports_unused_declarations() {
 	test_input!(TestInput {
 		code: r#"
-// don't transpile jsx with non-plain-object props
-import { jsx } from '@builder.io/qwik';
+import { component$ } from '@builder.io/qwik';
+import { Pool } from 'pg';
+import { format, escape } from './utils';
+import './polyfill';
+import { registerSW } from './sw';
 
-export const App = () => {
-    const props = {}
-    return jsx('div', props, 'Hello Qwik');
-}
-"#
-		.to_string(),
-		transpile_ts: false,
-		transpile_jsx: false,
-		..TestInput::default()
-	});
+const pool = new Pool();
+const _ = registerSW();
+
+function query(sql) {
+    return pool.query(escape(sql));
 }
 
-#[test]
-fn example_dev_mode() {
-	test_input!(TestInput {
-		code: r#"
-import { component$, useStore } from '@builder.io/qwik';
+export const onGet = () => query('select * from users');
 
-export const App = component$(() => {
-    return (
-        <Cmp>
-            <p class="stuff" onClick$={() => console.log('warn')}>Hello Qwik</p>
-        </Cmp>
-    );
+export default component$(() => {
+    return <div>{format('cmp')}</div>
 });
 "#
 		.to_string(),
-		mode: EmitMode::Dev,
-		transpile_ts: true,
-		transpile_jsx: true,
+		strip_exports: Some(vec!["onGet".into()]),
+		minify: MinifyMode::None,
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_dev_mode_inlined() {
+fn example_strip_server_code() {
 	test_input!(TestInput {
-		code: r#"
-import { component$, useStore } from '@builder.io/qwik';
+        code: r#"
+import { component$, serverLoader$, serverStuff$, $, client$, useStore, useTask$ } from '@builder.io/qwik';
+import { isServer } from '@builder.io/qwik/build';
+import mongo from 'mongodb';
+import redis from 'redis';
+import { handler } from 'serverless';
+
+export const Parent = component$(() => {
+    const state = useStore({
+        text: ''
+    });
+
+    // Double count watch
+    useTask$(async () => {
+        if (!isServer) return;
+        state.text = await mongo.users();
+        redis.set(state.text);
+    });
+
+    serverStuff$(async () => {
+        // should be removed too
+        const a = $(() => {
+            // from $(), should not be removed
+        });
+        const b = client$(() => {
+            // from clien$(), should not be removed
+        });
+        return [a,b];
+    })
+
+    serverLoader$(handler);
+
+    useTask$(() => {
+        // Code
+    });
 
-export const App = component$(() => {
     return (
-        <Cmp>
-            <p class="stuff" onClick$={() => console.log('warn')}>Hello Qwik</p>
-        </Cmp>
+        <div onClick$={() => console.log('parent')}>
+            {state.text}
+        </div>
     );
 });
 "#
-		.to_string(),
-		mode: EmitMode::Dev,
-		entry_strategy: EntryStrategy::Inline,
-		transpile_ts: true,
-		transpile_jsx: true,
-		..TestInput::default()
-	});
+        .to_string(),
+        transpile_ts: true,
+        transpile_jsx: true,
+        entry_strategy: EntryStrategy::Hook,
+        strip_ctx_name: Some(vec!["server".into()]),
+        ..TestInput::default()
+    });
 }
 
 #[test]
-fn example_transpile_jsx_only() {
+fn example_use_server_exports() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, useStore } from '@builder.io/qwik';
+import { component$, server$ } from '@builder.io/qwik';
+import { users } from './db.server';
+import { log } from './log.server';
 
-export const App = component$((props: Stuff) => {
-    return (
-        <Cmp>
-            <p class="stuff" onClick$={() => console.log('warn')}>Hello Qwik</p>
-        </Cmp>
-    );
+export async function getUsers() {
+    'use server';
+    return users.findAll();
+}
+
+export const deleteUser = async (id: string) => {
+    "use server";
+    await users.delete(id);
+};
+
+export const fetchUser = server$((id: string) => users.find(id));
+
+export const App = component$(() => {
+    return <button onClick$={() => log('clicked')}>Log</button>;
 });
 "#
 		.to_string(),
-		transpile_ts: false,
+		transpile_ts: true,
 		transpile_jsx: true,
-		explicit_extensions: true,
+		is_server: Some(false),
+		strip_ctx_name: Some(vec!["server".into()]),
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_spread_jsx() {
+fn example_server_only_module() {
 	test_input!(TestInput {
+		filename: "db.server.ts".into(),
 		code: r#"
-import { component$ } from '@builder.io/qwik';
-import { useDocumentHead, useLocation } from '@builder.io/qwik-city';
-
-/**
- * The RouterHead component is placed inside of the document `<head>` element.
- */
-export const RouterHead = component$(() => {
-  const head = useDocumentHead();
-  const loc = useLocation();
-
-  return (
-    <>
-      <title>{head.title}</title>
-
-      <link rel="canonical" href={loc.href} />
-      <meta name="viewport" content="width=device-width, initial-scale=1.0" />
-      <link rel="icon" type="image/svg+xml" href="/favicon.svg" />
-
-      {head.meta.map((m) => (
-        <meta {...m} />
-      ))}
+import { Pool } from 'pg';
 
-      {head.links.map((l) => (
-        <link {...l} key={l.key} />
-      ))}
+const pool = new Pool();
 
-      {head.styles.map((s) => (
-        <style {...s.props} dangerouslySetInnerHTML={s.style} key={s.key} />
-      ))}
-    </>
-  );
-});"#
-			.to_string(),
+export const users = {
+    findAll: () => pool.query('SELECT * FROM users'),
+};
+export function query(sql: string) {
+    return pool.query(sql);
+}
+export { pool as connection };
+export default pool;
+export type User = { id: string };
+"#
+		.to_string(),
 		transpile_ts: true,
-		transpile_jsx: true,
+		is_server: Some(false),
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_export_issue() {
+fn example_server_entry_not_server_only() {
 	test_input!(TestInput {
+		filename: "entry.server.tsx".into(),
 		code: r#"
-import { component$ } from '@builder.io/qwik';
-
-const App = component$(() => {
-    return (
-        <div>hola</div>
-    );
-});
-
-
-export const Root = component$((props: Stuff) => {
-    return (
-        <App/>
-    );
-});
-
-const Other = 12;
-export { Other as App };
+import { renderToStream } from '@builder.io/qwik/server';
+import { helpers } from './utils.server.test';
+import Root from './root';
 
-export default App;
+export default function (opts) {
+    return renderToStream(<Root />, { ...opts, helpers });
+}
 "#
 		.to_string(),
 		transpile_ts: true,
 		transpile_jsx: true,
+		is_server: Some(false),
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_jsx_keyed() {
+fn example_server_only_module_reexports() {
 	test_input!(TestInput {
+		filename: "db.server.ts".into(),
 		code: r#"
-import { component$, useStore } from '@builder.io/qwik';
+const pool = {};
 
-export const App = component$((props: Stuff) => {
-    return (
-        <>
-            <Cmp key="stuff"></Cmp>
-            <Cmp></Cmp>
-            <Cmp prop="23"></Cmp>
-            <Cmp prop="23" key={props.stuff}></Cmp>
-            <p key={props.stuff}>Hello Qwik</p>
-        </>
-    );
-});
+export enum Role {
+    Admin,
+    User,
+}
+export { pool as "db-pool", pool as "default" };
+export * from './queries';
+export * as migrations from './migrations';
+export type * from './types';
 "#
 		.to_string(),
 		transpile_ts: true,
-		transpile_jsx: true,
-		explicit_extensions: true,
+		is_server: Some(false),
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_jsx_keyed_dev() {
+fn example_server_auth() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, useStore } from '@builder.io/qwik';
+import GitHub from '@auth/core/providers/github'
+import Facebook from 'next-auth/providers/facebook'
+import Google from 'next-auth/providers/google'
+import {serverAuth$, auth$} from '@auth/qwik';
 
-export const App = component$((props: Stuff) => {
-    return (
-        <>
-            <Cmp key="stuff"></Cmp>
-            <Cmp></Cmp>
-            <Cmp prop="23"></Cmp>
-            <Cmp prop="23" key={props.stuff}></Cmp>
-            <p key={props.stuff}>Hello Qwik</p>
-        </>
-    );
+export const { onRequest, logout, getSession, signup } = serverAuth$({
+    providers: [
+    GitHub({
+        clientId: process.env.GITHUB_ID,
+        clientSecret: process.env.GITHUB_SECRET
+    }),
+    Facebook({
+        clientId: import.meta.env.FACEBOOK_ID,
+        clientSecret: import.meta.env.FACEBOOK_SECRET
+    }),
+    Google({
+        clientId: process.env.GOOGLE_ID,
+        clientSecret: process.env.GOOGLE_SECRET
+    })
+    ]
+});
+
+export const { onRequest, logout, getSession, signup } = auth$({
+    providers: [
+    GitHub({
+        clientId: process.env.GITHUB_ID,
+        clientSecret: process.env.GITHUB_SECRET
+    }),
+    Facebook({
+        clientId: process.env.FACEBOOK_ID,
+        clientSecret: process.env.FACEBOOK_SECRET
+    }),
+    Google({
+        clientId: process.env.GOOGLE_ID,
+        clientSecret: process.env.GOOGLE_SECRET
+    })
+    ]
 });
 "#
 		.to_string(),
-		filename: "project/index.tsx".into(),
-		src_dir: "/src/project".into(),
 		transpile_ts: true,
 		transpile_jsx: true,
-		mode: EmitMode::Dev,
-		explicit_extensions: true,
+		entry_strategy: EntryStrategy::Hook,
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_mutable_children() {
+fn example_strip_client_code() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, useStore, Slot, Fragment } from '@builder.io/qwik';
-import Image from './image.jpg?jsx';
-
-export function Fn1(props: Stuff) {
-    return (
-        <>
-            <div>{prop < 2 ? <p>1</p> : <Stuff>2</Stuff>}</div>
-        </>
-    );
-}
-
-export function Fn2(props: Stuff) {
-    return (
-        <div>{prop.value && <Stuff></Stuff>}<div></div></div>
-    );
-}
-
-export function Fn3(props: Stuff) {
-    if (prop.value) {
-        return (
-            <Stuff></Stuff>
-        );
-    }
-    return (
-        <div></div>
-    );
-}
-
-export function Fn4(props: Stuff) {
-    if (prop.value) {
-        return (
-            <div></div>
-        );
-    }
-    return (
-        <Stuff></Stuff>
-    );
-}
-
-export const Arrow = (props: Stuff) => <div>{prop < 2 ? <p>1</p> : <Stuff>2</Stuff>}</div>;
+import { component$, useClientMount$, useStore, useTask$ } from '@builder.io/qwik';
+import mongo from 'mongodb';
+import redis from 'redis';
+import threejs from 'threejs';
+import { a } from './keep';
+import { b } from '../keep2';
+import { c } from '../../remove';
 
-export const AppDynamic1 = component$((props: Stuff) => {
-    return (
-        <>
-            <div>{prop < 2 ? <p>1</p> : <Stuff>2</Stuff>}</div>
-        </>
-    );
-});
-export const AppDynamic2 = component$((props: Stuff) => {
-    return (
-        <div>{prop.value && <Stuff></Stuff>}<div></div></div>
-    );
-});
+export const Parent = component$(() => {
+    const state = useStore({
+        text: ''
+    });
 
-export const AppDynamic3 = component$((props: Stuff) => {
-    if (prop.value) {
-        return (
-            <Stuff></Stuff>
-        );
-    }
-    return (
-        <div></div>
-    );
-});
+    // Double count watch
+    useClientMount$(async () => {
+        state.text = await mongo.users();
+        redis.set(state.text, a, b, c);
+    });
 
-export const AppDynamic4 = component$((props: Stuff) => {
-    if (prop.value) {
-        return (
-            <div></div>
-        );
-    }
-    return (
-        <Stuff></Stuff>
-    );
-});
+    useTask$(() => {
+        // Code
+    });
 
-export const AppStatic = component$((props: Stuff) => {
     return (
-        <>
-            <div>Static {f ? 1 : 3}</div>
-            <div>{prop < 2 ? <p>1</p> : <p>2</p>}</div>
-
-            <div>{prop.value && <div></div>}</div>
-            <div>{prop.value && <Fragment><Slot></Slot></Fragment>}</div>
-            <div>{prop.value && <><div></div></>}</div>
-            <div>{prop.value && <Image/>}</div>
-            <div>Static {f ? 1 : 3}</div>
-            <div>Static</div>
-            <div>Static {props.value}</div>
-            <div>Static {stuff()}</div>
-            <div>Static {stuff()}</div>
-        </>
+        <div
+            shouldRemove$={() => state.text}
+            onClick$={() => console.log('parent', state, threejs)}
+        >
+            <Div
+                onClick$={() => console.log('keep')}
+                render$={() => state.text}
+            />
+            {state.text}
+        </div>
     );
 });
 "#
 		.to_string(),
-		entry_strategy: EntryStrategy::Hoist,
+		filename: "components/component.tsx".into(),
 		transpile_ts: true,
 		transpile_jsx: true,
-		explicit_extensions: true,
+		entry_strategy: EntryStrategy::Inline,
+		strip_ctx_name: Some(vec!["useClientMount$".into(),]),
+		strip_event_handlers: true,
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_immutable_function_components() {
+fn issue_150() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, useStore, Slot } from '@builder.io/qwik';
+import { component$, $ } from '@builder.io/qwik';
+import { hola } from 'sdfds';
 
-export const App = component$((props: Stuff) => {
-    return (
-        <div>
-            <Slot/>
-        </div>
-    );
+export const Greeter = component$(() => {
+    const stuff = useStore();
+    return $(() => {
+        return (
+            <div
+                class={{
+                    'foo': true,
+                    'bar': stuff.condition,
+                    'baz': hola ? 'true' : 'false',
+                }}
+            />
+        )
+    });
 });
+
+const d = $(()=>console.log('thing'));
 "#
 		.to_string(),
-		entry_strategy: EntryStrategy::Hoist,
 		transpile_ts: true,
 		transpile_jsx: true,
-		explicit_extensions: true,
 		..TestInput::default()
 	});
 }
+
 #[test]
-fn example_transpile_ts_only() {
+fn example_input_bind() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, useStore } from '@builder.io/qwik';
+import { component$, $ } from '@builder.io/qwik';
 
-export const App = component$((props: Stuff) => {
+export const Greeter = component$(() => {
+    const value = useSignal(0);
+    const checked = useSignal(false);
+    const stuff = useSignal();
     return (
-        <Cmp>
-            <p class="stuff" onClick$={() => console.log('warn')}>Hello Qwik</p>
-        </Cmp>
-    );
+        <>
+            <input bind:value={value} />
+            <input bind:checked={checked} />
+            <input bind:stuff={stuff} />
+            <div>{value}</div>
+            <div>{value.value}</div>
+        </>
+
+    )
 });
 "#
 		.to_string(),
 		entry_strategy: EntryStrategy::Inline,
 		transpile_ts: true,
-		transpile_jsx: false,
-		explicit_extensions: true,
+		transpile_jsx: true,
+		mode: EmitMode::Prod,
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_class_name() {
+fn example_import_assertion() {
 	test_input!(TestInput {
 		code: r#"
-import { component$ } from '@builder.io/qwik';
-
-export const App2 = component$(() => {
-    const signal = useSignal();
-    const computed = signal.value + 'foo';
-    return (
-        <>
-            <div className="hola"></div>
-            <div className={signal.value}></div>
-            <div className={signal}></div>
-            <div className={computed}></div>
+import { component$, $ } from '@builder.io/qwik';
+import json from "./foo.json" assert { type: "json" };
 
-            <Foo className="hola"></Foo>
-            <Foo className={signal.value}></Foo>
-            <Foo className={signal}></Foo>
-            <Foo className={computed}></Foo>
-        </>
-    );
+export const Greeter = component$(() => {
+    return json;
 });
 "#
 		.to_string(),
 		transpile_ts: true,
 		transpile_jsx: true,
-		explicit_extensions: true,
 		..TestInput::default()
 	});
 }
 
+#[cfg(target_os = "windows")]
 #[test]
-fn example_preserve_filenames() {
-	test_input!(TestInput {
+fn issue_188() {
+	let res = test_input!({
+		filename: r"components\apps\apps.tsx".to_string(),
+		src_dir: r"C:\users\apps".to_string(),
 		code: r#"
-import { component$, useStore } from '@builder.io/qwik';
+import { component$, $ } from '@builder.io/qwik';
 
-export const App = component$((props: Stuff) => {
-    return (
-        <Cmp>
-            <p class="stuff" onClick$={() => console.log('warn')}>Hello Qwik</p>
-        </Cmp>
-    );
+export const Greeter = component$(() => {
+    return $(() => {
+        return (
+            <div/>
+        )
+    });
 });
+
+const d = $(()=>console.log('thing'));
 "#
 		.to_string(),
-		entry_strategy: EntryStrategy::Inline,
-		transpile_ts: false,
+		transpile_ts: true,
 		transpile_jsx: true,
-		preserve_filenames: true,
-		explicit_extensions: true,
-		..TestInput::default()
-	});
+		snapshot: false,
+	})
+	.unwrap();
+	let last_module = res.modules.last().unwrap();
+	assert_eq!(last_module.path, r"C:/users/apps/components/apps/apps.tsx")
 }
-
 #[test]
-fn example_preserve_filenames_hooks() {
+fn issue_476() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, useStore } from '@builder.io/qwik';
+import { Counter } from "./counter.tsx";
 
-export const App = component$((props: Stuff) => {
-    foo();
+export const Root = () => {
     return (
-        <Cmp>
-            <p class="stuff" onClick$={() => console.log('warn')}>Hello Qwik</p>
-        </Cmp>
+        <html>
+            <head>
+                <meta charset="utf-8" />
+                <title>Qwik Blank App</title>
+            </head>
+            <body>
+                <Counter initial={3} />
+            </body>
+        </html>
     );
-});
-
-export const foo = () => console.log('foo');
+};
 "#
 		.to_string(),
-		entry_strategy: EntryStrategy::Hook,
-		transpile_ts: true,
-		transpile_jsx: true,
-		preserve_filenames: true,
-		explicit_extensions: true,
+		transpile_ts: false,
+		transpile_jsx: false,
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_build_server() {
+fn issue_964() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, useStore } from '@builder.io/qwik';
-import { isServer, isBrowser } from '@builder.io/qwik/build';
-import { mongodb } from 'mondodb';
-import { threejs } from 'threejs';
-
-import L from 'leaflet';
-
-export const functionThatNeedsWindow = () => {
-  if (isBrowser) {
-    console.log('l', L);
-    console.log('hey');
-    window.alert('hey');
-  }
-};
+import { component$ } from '@builder.io/qwik';
 
 export const App = component$(() => {
-    useMount$(() => {
-        if (isServer) {
-            console.log('server', mongodb());
-        }
-        if (isBrowser) {
-            console.log('browser', new threejs());
-        }
+    console.log(function*(lo: any, t: any) {
+    console.log(yield (yield lo)(t.href).then((r) => r.json()));
     });
-    return (
-        <Cmp>
-            {isServer && <p>server</p>}
-            {isBrowser && <p>server</p>}
-        </Cmp>
-    );
+
+    return <p>Hello Qwik</p>;
 });
 "#
 		.to_string(),
-		is_server: Some(true),
-		mode: EmitMode::Prod,
+		transpile_ts: true,
+		transpile_jsx: true,
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_derived_signals_div() {
+fn example_immutable_analysis() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, useStore, mutable } from '@builder.io/qwik';
-
-import {dep} from './file';
+import { component$, useStore, $ } from '@builder.io/qwik';
+import importedValue from 'v';
 import styles from './styles.module.css';
 
 export const App = component$((props) => {
-    const signal = useSignal(0);
-    const store = useStore({});
-    const count = props.counter.count;
-
+    const {Model} = props;
+    const state = useStore({count: 0});
+    const remove = $((id: number) => {
+        const d = state.data;
+        d.splice(
+          d.findIndex((d) => d.id === id),
+          1
+        )
+      });
     return (
-        <div
-            class={{
-                even: count % 2 === 0,
-                odd: count % 2 === 1,
-                stable0: true,
-                hidden: false,
-            }}
-            staticClass={styles.foo}
-            staticDocument={window.document}
-            staticText="text"
-            staticText2={`text`}
-            staticNumber={1}
-            staticBoolean={true}
-            staticExpr={`text${12}`}
-            staticExpr2={typeof `text${12}` === 'string' ? 12 : 43}
-
-            signal={signal}
-            signalValue={signal.value}
-            signalComputedValue={12 + signal.value}
-
-            store={store.address.city.name}
-            storeComputed={store.address.city.name ? 'true' : 'false'}
-
-            dep={dep}
-            depAccess={dep.thing}
-            depComputed={dep.thing + 'stuff'}
-
-            global={globalThing}
-            globalAccess={globalThing.thing}
-            globalComputed={globalThing.thing + 'stuff'}
-
-
-            noInline={signal.value()}
-            noInline2={signal.value + unknown()}
-            noInline3={mutable(signal)}
-            noInline4={signal.value + dep}
-        />
-
+        <>
+            <p class="stuff" onClick$={props.onClick$}>Hello Qwik</p>
+            <Div
+                class={styles.foo}
+                document={window.document}
+                onClick$={props.onClick$}
+                onEvent$={() => console.log('stuff')}
+                transparent$={() => {console.log('stuff')}}
+                immutable1="stuff"
+                immutable2={{
+                    foo: 'bar',
+                    baz: importedValue ? true : false,
+                }}
+                immutable3={2}
+                immutable4$={(ev) => console.log(state.count)}
+                immutable5={[1, 2, importedValue, null, {}]}
+            >
+                <p>Hello Qwik</p>
+            </Div>
+            [].map(() => (
+                <Model
+                    class={state}
+                    remove$={remove}
+                    mutable1={{
+                        foo: 'bar',
+                        baz: state.count ? true : false,
+                    }}
+                    mutable2={(() => console.log(state.count))()}
+                    mutable3={[1, 2, state, null, {}]}
+                />
+            ));
+        </>
     );
 });
 "#
 		.to_string(),
-		transpile_jsx: true,
 		transpile_ts: true,
-		entry_strategy: EntryStrategy::Hoist,
+		transpile_jsx: true,
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_issue_4438() {
+fn example_ts_enums_issue_1341() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, useSignal } from '@builder.io/qwik';
+import { component$, useStore } from '@builder.io/qwik';
+
+enum Thing {
+    A,
+    B
+}
 
 export const App = component$(() => {
-    const toggle = useSignal(false);
+    console.log(Thing.A);
     return (
         <>
-            <div data-nu={toggle.value ? $localize`singular` : 'plural'}></div>
-            <div>{toggle.value ? $localize`singular` : $localize`plural`}</div>
+            <p class="stuff">Hello Qwik</p>
         </>
     );
 });
 "#
 		.to_string(),
-		transpile_jsx: true,
 		transpile_ts: true,
-		entry_strategy: EntryStrategy::Hoist,
+		transpile_jsx: true,
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_derived_signals_children() {
+fn example_ts_enums_no_transpile() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, useStore, mutable } from '@builder.io/qwik';
-
-import {dep} from './file';
+import { component$, useStore } from '@builder.io/qwik';
 
-export const TextContent = component$((props) => {
-    return (
-        <>
-            <div>data-nu: {props['data-nu']}</div>
-            <div>class: {props.class}</div>
-        </>
-    );
-});
+export enum Thing {
+    A,
+    B
+}
 
 export const App = component$(() => {
-    const signal = useSignal(0);
-    const store = useStore({});
+    console.log(Thing.A);
     return (
         <>
-            <div>text</div>
-            <div>{`text`}</div>
-            <div>{1}</div>
-            <div>{true}</div>
-            <div>{`text${12}`}</div>
-            <div>{typeof `text${12}` === 'string' ? 12 : 43}</div>
-            <div>{signal}</div>
-            <div>{signal.value}</div>
-            <div>{12 + signal.value}</div>
-            <div>{store.address.city.name}</div>
-            <div>{store.address.city.name ? 'true' : 'false'}</div>
-            <div>{dep}</div>
-            <div>{dep.thing}</div>
-            <div>{dep.thing + 'stuff'}</div>
-            <div>{globalThing}</div>
-            <div>{globalThing.thing}</div>
-            <div>{globalThing.thing + 'stuff'}</div>
-            <div>{signal.value()}</div>
-            <div>{signal.value + unknown()}</div>
-            <div>{mutable(signal)}</div>
-            <div>{signal.value + dep}</div>
+            <p class="stuff">Hello Qwik</p>
         </>
     );
 });
 "#
 		.to_string(),
-		transpile_jsx: true,
-		transpile_ts: true,
-		entry_strategy: EntryStrategy::Hoist,
+		transpile_ts: false,
+		transpile_jsx: false,
+
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_derived_signals_multiple_children() {
+fn example_ts_enums() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, useStore, mutable } from '@builder.io/qwik';
+import { component$, useStore } from '@builder.io/qwik';
 
-import {dep} from './file';
+export enum Thing {
+    A,
+    B
+}
 
 export const App = component$(() => {
-    const signal = useSignal(0);
-    const store = useStore({});
+    console.log(Thing.A);
     return (
         <>
-            <div>First text</div>
-            <div>First {`text`}</div>
-            <div>First {1}</div>
-            <div>First {true}</div>
-            <div>First {`text${12}`}</div>
-            <div>First {typeof `text${12}` === 'string' ? 12 : 43}</div>
-            <div>First {signal}</div>
-            <div>First {signal.value}</div>
-            <div>First {12 + signal.value}</div>
-            <div>First {store.address.city.name}</div>
-            <div>First {store.address.city.name ? 'true' : 'false'}</div>
-            <div>First {dep}</div>
-            <div>First {dep.thing}</div>
-            <div>First {dep.thing + 'stuff'}</div>
-            <div>First {globalThing}</div>
-            <div>First {globalThing.thing}</div>
-            <div>First {globalThing.thing + 'stuff'}</div>
-            <div>First {signal.value()}</div>
-            <div>First {signal.value + unknown()}</div>
-            <div>First {mutable(signal)}</div>
-            <div>First {signal.value + dep}</div>
+            <p class="stuff">Hello Qwik</p>
         </>
     );
 });
 "#
 		.to_string(),
-		transpile_jsx: true,
 		transpile_ts: true,
-		entry_strategy: EntryStrategy::Hoist,
+		transpile_jsx: true,
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_derived_signals_complext_children() {
+fn special_jsx() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, useStore, mutable } from '@builder.io/qwik';
+// don't transpile jsx with non-plain-object props
+import { jsx } from '@builder.io/qwik';
 
-import {dep} from './file';
+export const App = () => {
+    const props = {}
+    return jsx('div', props, 'Hello Qwik');
+}
+"#
+		.to_string(),
+		transpile_ts: false,
+		transpile_jsx: false,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_dev_mode() {
+	test_input!(TestInput {
+		code: r#"
+import { component$, useStore } from '@builder.io/qwik';
 
 export const App = component$(() => {
-    const signal = useSignal(0);
-    const store = useStore({});
     return (
-        <>
-            <ul id="issue-2800-result">
-                {Object.entries(store).map(([key, value]) => (
-                <li>
-                    {key} - {value}
-                </li>
-                ))}
-            </ul>
-        </>
+        <Cmp>
+            <p class="stuff" onClick$={() => console.log('warn')}>Hello Qwik</p>
+        </Cmp>
     );
 });
 "#
 		.to_string(),
-		transpile_jsx: true,
+		mode: EmitMode::Dev,
 		transpile_ts: true,
-		entry_strategy: EntryStrategy::Hoist,
+		transpile_jsx: true,
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_derived_signals_cmp() {
+fn example_dev_mode_inlined() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, useStore, mutable } from '@builder.io/qwik';
-
-import {dep} from './file';
-import {Cmp} from './cmp';
+import { component$, useStore } from '@builder.io/qwik';
 
 export const App = component$(() => {
-    const signal = useSignal(0);
-    const store = useStore({});
     return (
-        <Cmp
-            staticText="text"
-            staticText2={`text`}
-            staticNumber={1}
-            staticBoolean={true}
-            staticExpr={`text${12}`}
-            staticExpr2={typeof `text${12}` === 'string' ? 12 : 43}
-
-            signal={signal}
-            signalValue={signal.value}
-            signalComputedValue={12 + signal.value}
-
-            store={store.address.city.name}
-            storeComputed={store.address.city.name ? 'true' : 'false'}
-
-            dep={dep}
-            depAccess={dep.thing}
-            depComputed={dep.thing + 'stuff'}
-
-            global={globalThing}
-            globalAccess={globalThing.thing}
-            globalComputed={globalThing.thing + 'stuff'}
+        <Cmp>
+            <p class="stuff" onClick$={() => console.log('warn')}>Hello Qwik</p>
+        </Cmp>
+    );
+});
+"#
+		.to_string(),
+		mode: EmitMode::Dev,
+		entry_strategy: EntryStrategy::Inline,
+		transpile_ts: true,
+		transpile_jsx: true,
+		..TestInput::default()
+	});
+}
 
+#[test]
+fn example_transpile_jsx_only() {
+	test_input!(TestInput {
+		code: r#"
+import { component$, useStore } from '@builder.io/qwik';
 
-            noInline={signal.value()}
-            noInline2={signal.value + unknown()}
-            noInline3={mutable(signal)}
-            noInline4={signal.value + dep}
-        />
+export const App = component$((props: Stuff) => {
+    return (
+        <Cmp>
+            <p class="stuff" onClick$={() => console.log('warn')}>Hello Qwik</p>
+        </Cmp>
     );
 });
 "#
 		.to_string(),
+		transpile_ts: false,
 		transpile_jsx: true,
-		transpile_ts: true,
-		entry_strategy: EntryStrategy::Hoist,
+		explicit_extensions: true,
 		..TestInput::default()
 	});
 }
 
 #[test]
-fn example_issue_33443() {
+fn example_spread_jsx() {
 	test_input!(TestInput {
-        code: r#"
-import { component$, useSignal } from '@builder.io/qwik';
+		code: r#"
+import { component$ } from '@builder.io/qwik';
+import { useDocumentHead, useLocation } from '@builder.io/qwik-city';
 
-export const Issue3742 = component$(({description = '', other}: any) => {
-    const counter = useSignal(0);
-    return (
-      <div
-        title={(description && 'description' in other) ? `Hello ${counter.value}` : `Bye ${counter.value}`}
-      >
-        Issue3742
-        <button onClick$={() => counter.value++}>
-          Increment
-        </button>
-      </div>
-    )
-  });
-  "#
-        .to_string(),
-        transpile_jsx: true,
-        transpile_ts: true,
-        entry_strategy: EntryStrategy::Hoist,
-        ..TestInput::default()
-    });
+/**
+ * The RouterHead component is placed inside of the document `<head>` element.
+ */
+export const RouterHead = component$(() => {
+  const head = useDocumentHead();
+  const loc = useLocation();
+
+  return (
+    <>
+      <title>{head.title}</title>
+
+      <link rel="canonical" href={loc.href} />
+      <meta name="viewport" content="width=device-width, initial-scale=1.0" />
+      <link rel="icon" type="image/svg+xml" href="/favicon.svg" />
+
+      {head.meta.map((m) => (
+        <meta {...m} />
+      ))}
+
+      {head.links.map((l) => (
+        <link {...l} key={l.key} />
+      ))}
+
+      {head.styles.map((s) => (
+        <style {...s.props} dangerouslySetInnerHTML={s.style} key={s.key} />
+      ))}
+    </>
+  );
+});"#
+			.to_string(),
+		transpile_ts: true,
+		transpile_jsx: true,
+		..TestInput::default()
+	});
 }
+
 #[test]
-fn example_getter_generation() {
+fn example_export_issue() {
 	test_input!(TestInput {
 		code: r#"
-import { component$, useStore } from '@builder.io/qwik';
+import { component$ } from '@builder.io/qwik';
 
-export const App = component$(() => {
-    const store = useStore({
-        count: 0,
-        stuff: 0,
-        nested: {
-            count: 0
-        }
-    });
-    const signal = useSignal(0);
+const App = component$(() => {
     return (
-        <Cmp
-            prop={'true' + 1 ? 'true' : ''}
-            count={store.count}
-            nested={store.nested.count}
-            signal={signal}
-            store={store.stuff + 12}
-            value={signal.formData?.get('username')}
-        >
-        </Cmp>
+        <div>hola</div>
     );
 });
 
-export const Cmp = component$((props) => {
+
+export const Root = component$((props: Stuff) => {
     return (
-        <>
-            <p data-value={props.count}>{props.nested.count}</p>
-            <p>Value {props.count}<span></span></p>
-        </>
+        <App/>
     );
 });
+
+const Other = 12;
+export { Other as App };
+
+export default App;
 "#
 		.to_string(),
 		transpile_ts: true,
@@ -3002,20 +3044,802 @@ export const Cmp = component$((props) => {
 }
 
 #[test]
-fn example_qwik_react() {
+fn example_jsx_keyed() {
 	test_input!(TestInput {
-        code: r#"
-import { componentQrl, inlinedQrl, useLexicalScope, useHostElement, useStore, useTaskQrl, noSerialize, SkipRerender, implicit$FirstArg } from '@builder.io/qwik';
-import { jsx, Fragment } from '@builder.io/qwik/jsx-runtime';
-import { isBrowser, isServer } from '@builder.io/qwik/build';
+		code: r#"
+import { component$, useStore } from '@builder.io/qwik';
 
-function qwikifyQrl(reactCmpQrl) {
-    return /*#__PURE__*/ componentQrl(inlinedQrl((props)=>{
-        const [reactCmpQrl] = useLexicalScope();
-        const hostElement = useHostElement();
-        const store = useStore({});
-        let run;
-        if (props['client:visible']) run = 'visible';
+export const App = component$((props: Stuff) => {
+    return (
+        <>
+            <Cmp key="stuff"></Cmp>
+            <Cmp></Cmp>
+            <Cmp prop="23"></Cmp>
+            <Cmp prop="23" key={props.stuff}></Cmp>
+            <p key={props.stuff}>Hello Qwik</p>
+        </>
+    );
+});
+"#
+		.to_string(),
+		transpile_ts: true,
+		transpile_jsx: true,
+		explicit_extensions: true,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_jsx_keyed_dev() {
+	test_input!(TestInput {
+		code: r#"
+import { component$, useStore } from '@builder.io/qwik';
+
+export const App = component$((props: Stuff) => {
+    return (
+        <>
+            <Cmp key="stuff"></Cmp>
+            <Cmp></Cmp>
+            <Cmp prop="23"></Cmp>
+            <Cmp prop="23" key={props.stuff}></Cmp>
+            <p key={props.stuff}>Hello Qwik</p>
+        </>
+    );
+});
+"#
+		.to_string(),
+		filename: "project/index.tsx".into(),
+		src_dir: "/src/project".into(),
+		transpile_ts: true,
+		transpile_jsx: true,
+		mode: EmitMode::Dev,
+		explicit_extensions: true,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_mutable_children() {
+	test_input!(TestInput {
+		code: r#"
+import { component$, useStore, Slot, Fragment } from '@builder.io/qwik';
+import Image from './image.jpg?jsx';
+
+export function Fn1(props: Stuff) {
+    return (
+        <>
+            <div>{prop < 2 ? <p>1</p> : <Stuff>2</Stuff>}</div>
+        </>
+    );
+}
+
+export function Fn2(props: Stuff) {
+    return (
+        <div>{prop.value && <Stuff></Stuff>}<div></div></div>
+    );
+}
+
+export function Fn3(props: Stuff) {
+    if (prop.value) {
+        return (
+            <Stuff></Stuff>
+        );
+    }
+    return (
+        <div></div>
+    );
+}
+
+export function Fn4(props: Stuff) {
+    if (prop.value) {
+        return (
+            <div></div>
+        );
+    }
+    return (
+        <Stuff></Stuff>
+    );
+}
+
+export const Arrow = (props: Stuff) => <div>{prop < 2 ? <p>1</p> : <Stuff>2</Stuff>}</div>;
+
+export const AppDynamic1 = component$((props: Stuff) => {
+    return (
+        <>
+            <div>{prop < 2 ? <p>1</p> : <Stuff>2</Stuff>}</div>
+        </>
+    );
+});
+export const AppDynamic2 = component$((props: Stuff) => {
+    return (
+        <div>{prop.value && <Stuff></Stuff>}<div></div></div>
+    );
+});
+
+export const AppDynamic3 = component$((props: Stuff) => {
+    if (prop.value) {
+        return (
+            <Stuff></Stuff>
+        );
+    }
+    return (
+        <div></div>
+    );
+});
+
+export const AppDynamic4 = component$((props: Stuff) => {
+    if (prop.value) {
+        return (
+            <div></div>
+        );
+    }
+    return (
+        <Stuff></Stuff>
+    );
+});
+
+export const AppStatic = component$((props: Stuff) => {
+    return (
+        <>
+            <div>Static {f ? 1 : 3}</div>
+            <div>{prop < 2 ? <p>1</p> : <p>2</p>}</div>
+
+            <div>{prop.value && <div></div>}</div>
+            <div>{prop.value && <Fragment><Slot></Slot></Fragment>}</div>
+            <div>{prop.value && <><div></div></>}</div>
+            <div>{prop.value && <Image/>}</div>
+            <div>Static {f ? 1 : 3}</div>
+            <div>Static</div>
+            <div>Static {props.value}</div>
+            <div>Static {stuff()}</div>
+            <div>Static {stuff()}</div>
+        </>
+    );
+});
+"#
+		.to_string(),
+		entry_strategy: EntryStrategy::Hoist,
+		transpile_ts: true,
+		transpile_jsx: true,
+		explicit_extensions: true,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_immutable_function_components() {
+	test_input!(TestInput {
+		code: r#"
+import { component$, useStore, Slot } from '@builder.io/qwik';
+
+export const App = component$((props: Stuff) => {
+    return (
+        <div>
+            <Slot/>
+        </div>
+    );
+});
+"#
+		.to_string(),
+		entry_strategy: EntryStrategy::Hoist,
+		transpile_ts: true,
+		transpile_jsx: true,
+		explicit_extensions: true,
+		..TestInput::default()
+	});
+}
+#[test]
+fn example_transpile_ts_only() {
+	test_input!(TestInput {
+		code: r#"
+import { component$, useStore } from '@builder.io/qwik';
+
+export const App = component$((props: Stuff) => {
+    return (
+        <Cmp>
+            <p class="stuff" onClick$={() => console.log('warn')}>Hello Qwik</p>
+        </Cmp>
+    );
+});
+"#
+		.to_string(),
+		entry_strategy: EntryStrategy::Inline,
+		transpile_ts: true,
+		transpile_jsx: false,
+		explicit_extensions: true,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_class_name() {
+	test_input!(TestInput {
+		code: r#"
+import { component$ } from '@builder.io/qwik';
+
+export const App2 = component$(() => {
+    const signal = useSignal();
+    const computed = signal.value + 'foo';
+    return (
+        <>
+            <div className="hola"></div>
+            <div className={signal.value}></div>
+            <div className={signal}></div>
+            <div className={computed}></div>
+
+            <Foo className="hola"></Foo>
+            <Foo className={signal.value}></Foo>
+            <Foo className={signal}></Foo>
+            <Foo className={computed}></Foo>
+        </>
+    );
+});
+"#
+		.to_string(),
+		transpile_ts: true,
+		transpile_jsx: true,
+		explicit_extensions: true,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_preserve_filenames() {
+	test_input!(TestInput {
+		code: r#"
+import { component$, useStore } from '@builder.io/qwik';
+
+export const App = component$((props: Stuff) => {
+    return (
+        <Cmp>
+            <p class="stuff" onClick$={() => console.log('warn')}>Hello Qwik</p>
+        </Cmp>
+    );
+});
+"#
+		.to_string(),
+		entry_strategy: EntryStrategy::Inline,
+		transpile_ts: false,
+		transpile_jsx: true,
+		preserve_filenames: true,
+		explicit_extensions: true,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_preserve_filenames_hooks() {
+	test_input!(TestInput {
+		code: r#"
+import { component$, useStore } from '@builder.io/qwik';
+
+export const App = component$((props: Stuff) => {
+    foo();
+    return (
+        <Cmp>
+            <p class="stuff" onClick$={() => console.log('warn')}>Hello Qwik</p>
+        </Cmp>
+    );
+});
+
+export const foo = () => console.log('foo');
+"#
+		.to_string(),
+		entry_strategy: EntryStrategy::Hook,
+		transpile_ts: true,
+		transpile_jsx: true,
+		preserve_filenames: true,
+		explicit_extensions: true,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_build_server() {
+	test_input!(TestInput {
+		code: r#"
+import { component$, useStore } from '@builder.io/qwik';
+import { isServer, isBrowser } from '@builder.io/qwik/build';
+import { mongodb } from 'mondodb';
+import { threejs } from 'threejs';
+
+import L from 'leaflet';
+
+export const functionThatNeedsWindow = () => {
+  if (isBrowser) {
+    console.log('l', L);
+    console.log('hey');
+    window.alert('hey');
+  }
+};
+
+export const App = component$(() => {
+    useMount$(() => {
+        if (isServer) {
+            console.log('server', mongodb());
+        }
+        if (isBrowser) {
+            console.log('browser', new threejs());
+        }
+    });
+    return (
+        <Cmp>
+            {isServer && <p>server</p>}
+            {isBrowser && <p>server</p>}
+        </Cmp>
+    );
+});
+"#
+		.to_string(),
+		is_server: Some(true),
+		mode: EmitMode::Prod,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_derived_signals_div() {
+	test_input!(TestInput {
+		code: r#"
+import { component$, useStore, mutable } from '@builder.io/qwik';
+
+import {dep} from './file';
+import styles from './styles.module.css';
+
+export const App = component$((props) => {
+    const signal = useSignal(0);
+    const store = useStore({});
+    const count = props.counter.count;
+
+    return (
+        <div
+            class={{
+                even: count % 2 === 0,
+                odd: count % 2 === 1,
+                stable0: true,
+                hidden: false,
+            }}
+            staticClass={styles.foo}
+            staticDocument={window.document}
+            staticText="text"
+            staticText2={`text`}
+            staticNumber={1}
+            staticBoolean={true}
+            staticExpr={`text${12}`}
+            staticExpr2={typeof `text${12}` === 'string' ? 12 : 43}
+
+            signal={signal}
+            signalValue={signal.value}
+            signalComputedValue={12 + signal.value}
+
+            store={store.address.city.name}
+            storeComputed={store.address.city.name ? 'true' : 'false'}
+
+            dep={dep}
+            depAccess={dep.thing}
+            depComputed={dep.thing + 'stuff'}
+
+            global={globalThing}
+            globalAccess={globalThing.thing}
+            globalComputed={globalThing.thing + 'stuff'}
+
+
+            noInline={signal.value()}
+            noInline2={signal.value + unknown()}
+            noInline3={mutable(signal)}
+            noInline4={signal.value + dep}
+        />
+
+    );
+});
+"#
+		.to_string(),
+		transpile_jsx: true,
+		transpile_ts: true,
+		entry_strategy: EntryStrategy::Hoist,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_issue_4438() {
+	test_input!(TestInput {
+		code: r#"
+import { component$, useSignal } from '@builder.io/qwik';
+
+export const App = component$(() => {
+    const toggle = useSignal(false);
+    return (
+        <>
+            <div data-nu={toggle.value ? $localize`singular` : 'plural'}></div>
+            <div>{toggle.value ? $localize`singular` : $localize`plural`}</div>
+        </>
+    );
+});
+"#
+		.to_string(),
+		transpile_jsx: true,
+		transpile_ts: true,
+		entry_strategy: EntryStrategy::Hoist,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_derived_signals_children() {
+	test_input!(TestInput {
+		code: r#"
+import { component$, useStore, mutable } from '@builder.io/qwik';
+
+import {dep} from './file';
+
+export const TextContent = component$((props) => {
+    return (
+        <>
+            <div>data-nu: {props['data-nu']}</div>
+            <div>class: {props.class}</div>
+        </>
+    );
+});
+
+export const App = component$(() => {
+    const signal = useSignal(0);
+    const store = useStore({});
+    return (
+        <>
+            <div>text</div>
+            <div>{`text`}</div>
+            <div>{1}</div>
+            <div>{true}</div>
+            <div>{`text${12}`}</div>
+            <div>{typeof `text${12}` === 'string' ? 12 : 43}</div>
+            <div>{signal}</div>
+            <div>{signal.value}</div>
+            <div>{12 + signal.value}</div>
+            <div>{store.address.city.name}</div>
+            <div>{store.address.city.name ? 'true' : 'false'}</div>
+            <div>{dep}</div>
+            <div>{dep.thing}</div>
+            <div>{dep.thing + 'stuff'}</div>
+            <div>{globalThing}</div>
+            <div>{globalThing.thing}</div>
+            <div>{globalThing.thing + 'stuff'}</div>
+            <div>{signal.value()}</div>
+            <div>{signal.value + unknown()}</div>
+            <div>{mutable(signal)}</div>
+            <div>{signal.value + dep}</div>
+        </>
+    );
+});
+"#
+		.to_string(),
+		transpile_jsx: true,
+		transpile_ts: true,
+		entry_strategy: EntryStrategy::Hoist,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_derived_signals_multiple_children() {
+	test_input!(TestInput {
+		code: r#"
+import { component$, useStore, mutable } from '@builder.io/qwik';
+
+import {dep} from './file';
+
+export const App = component$(() => {
+    const signal = useSignal(0);
+    const store = useStore({});
+    return (
+        <>
+            <div>First text</div>
+            <div>First {`text`}</div>
+            <div>First {1}</div>
+            <div>First {true}</div>
+            <div>First {`text${12}`}</div>
+            <div>First {typeof `text${12}` === 'string' ? 12 : 43}</div>
+            <div>First {signal}</div>
+            <div>First {signal.value}</div>
+            <div>First {12 + signal.value}</div>
+            <div>First {store.address.city.name}</div>
+            <div>First {store.address.city.name ? 'true' : 'false'}</div>
+            <div>First {dep}</div>
+            <div>First {dep.thing}</div>
+            <div>First {dep.thing + 'stuff'}</div>
+            <div>First {globalThing}</div>
+            <div>First {globalThing.thing}</div>
+            <div>First {globalThing.thing + 'stuff'}</div>
+            <div>First {signal.value()}</div>
+            <div>First {signal.value + unknown()}</div>
+            <div>First {mutable(signal)}</div>
+            <div>First {signal.value + dep}</div>
+        </>
+    );
+});
+"#
+		.to_string(),
+		transpile_jsx: true,
+		transpile_ts: true,
+		entry_strategy: EntryStrategy::Hoist,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_derived_signals_complext_children() {
+	test_input!(TestInput {
+		code: r#"
+import { component$, useStore, mutable } from '@builder.io/qwik';
+
+import {dep} from './file';
+
+export const App = component$(() => {
+    const signal = useSignal(0);
+    const store = useStore({});
+    return (
+        <>
+            <ul id="issue-2800-result">
+                {Object.entries(store).map(([key, value]) => (
+                <li>
+                    {key} - {value}
+                </li>
+                ))}
+            </ul>
+        </>
+    );
+});
+"#
+		.to_string(),
+		transpile_jsx: true,
+		transpile_ts: true,
+		entry_strategy: EntryStrategy::Hoist,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_derived_signals_cmp() {
+	test_input!(TestInput {
+		code: r#"
+import { component$, useStore, mutable } from '@builder.io/qwik';
+
+import {dep} from './file';
+import {Cmp} from './cmp';
+
+export const App = component$(() => {
+    const signal = useSignal(0);
+    const store = useStore({});
+    return (
+        <Cmp
+            staticText="text"
+            staticText2={`text`}
+            staticNumber={1}
+            staticBoolean={true}
+            staticExpr={`text${12}`}
+            staticExpr2={typeof `text${12}` === 'string' ? 12 : 43}
+
+            signal={signal}
+            signalValue={signal.value}
+            signalComputedValue={12 + signal.value}
+
+            store={store.address.city.name}
+            storeComputed={store.address.city.name ? 'true' : 'false'}
+
+            dep={dep}
+            depAccess={dep.thing}
+            depComputed={dep.thing + 'stuff'}
+
+            global={globalThing}
+            globalAccess={globalThing.thing}
+            globalComputed={globalThing.thing + 'stuff'}
+
+
+            noInline={signal.value()}
+            noInline2={signal.value + unknown()}
+            noInline3={mutable(signal)}
+            noInline4={signal.value + dep}
+        />
+    );
+});
+"#
+		.to_string(),
+		transpile_jsx: true,
+		transpile_ts: true,
+		entry_strategy: EntryStrategy::Hoist,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_issue_33443() {
+	test_input!(TestInput {
+        code: r#"
+import { component$, useSignal } from '@builder.io/qwik';
+
+export const Issue3742 = component$(({description = '', other}: any) => {
+    const counter = useSignal(0);
+    return (
+      <div
+        title={(description && 'description' in other) ? `Hello ${counter.value}` : `Bye ${counter.value}`}
+      >
+        Issue3742
+        <button onClick$={() => counter.value++}>
+          Increment
+        </button>
+      </div>
+    )
+  });
+  "#
+        .to_string(),
+        transpile_jsx: true,
+        transpile_ts: true,
+        entry_strategy: EntryStrategy::Hoist,
+        ..TestInput::default()
+    });
+}
+#[test]
+fn example_getter_generation() {
+	test_input!(TestInput {
+		code: r#"
+import { component$, useStore } from '@builder.io/qwik';
+
+export const App = component$(() => {
+    const store = useStore({
+        count: 0,
+        stuff: 0,
+        nested: {
+            count: 0
+        }
+    });
+    const signal = useSignal(0);
+    return (
+        <Cmp
+            prop={'true' + 1 ? 'true' : ''}
+            count={store.count}
+            nested={store.nested.count}
+            signal={signal}
+            store={store.stuff + 12}
+            value={signal.formData?.get('username')}
+        >
+        </Cmp>
+    );
+});
+
+export const Cmp = component$((props) => {
+    return (
+        <>
+            <p data-value={props.count}>{props.nested.count}</p>
+            <p>Value {props.count}<span></span></p>
+        </>
+    );
+});
+"#
+		.to_string(),
+		transpile_ts: true,
+		transpile_jsx: true,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn example_qwik_react() {
+	test_input!(TestInput {
+        code: r#"
+import { componentQrl, inlinedQrl, useLexicalScope, useHostElement, useStore, useTaskQrl, noSerialize, SkipRerender, implicit$FirstArg } from '@builder.io/qwik';
+import { jsx, Fragment } from '@builder.io/qwik/jsx-runtime';
+import { isBrowser, isServer } from '@builder.io/qwik/build';
+
+function qwikifyQrl(reactCmpQrl) {
+    return /*#__PURE__*/ componentQrl(inlinedQrl((props)=>{
+        const [reactCmpQrl] = useLexicalScope();
+        const hostElement = useHostElement();
+        const store = useStore({});
+        let run;
+        if (props['client:visible']) run = 'visible';
+        else if (props['client:load'] || props['client:only']) run = 'load';
+        useTaskQrl(inlinedQrl(async (track)=>{
+            const [hostElement, props, reactCmpQrl, store] = useLexicalScope();
+            track(props);
+            if (isBrowser) {
+                if (store.data) store.data.root.render(store.data.client.Main(store.data.cmp, filterProps(props)));
+                else {
+                    const [Cmp, client] = await Promise.all([
+                        reactCmpQrl.resolve(),
+                        import('./client-f762f78c.js')
+                    ]);
+                    let root;
+                    if (hostElement.childElementCount > 0) root = client.hydrateRoot(hostElement, client.Main(Cmp, filterProps(props), store.event));
+                    else {
+                        root = client.createRoot(hostElement);
+                        root.render(client.Main(Cmp, filterProps(props)));
+                    }
+                    store.data = noSerialize({
+                        client,
+                        cmp: Cmp,
+                        root
+                    });
+                }
+            }
+        }, "qwikifyQrl_component_useWatch_x04JC5xeP1U", [
+            hostElement,
+            props,
+            reactCmpQrl,
+            store
+        ]), {
+            run
+        });
+        if (isServer && !props['client:only']) {
+            const jsx$1 = Promise.all([
+                reactCmpQrl.resolve(),
+                import('./server-9ac6caad.js')
+            ]).then(([Cmp, server])=>{
+                const html = server.render(Cmp, filterProps(props));
+                return /*#__PURE__*/ jsx(Host, {
+                    dangerouslySetInnerHTML: html,
+                    [_IMMUTABLE]: [
+                        "dangerouslySetInnerHTML"
+                    ]
+                });
+            });
+            return /*#__PURE__*/ jsx(Fragment, {
+                children: jsx$1
+            });
+        }
+        return /*#__PURE__*/ jsx(Host, {
+            children: /*#__PURE__*/ jsx(SkipRerender, {})
+        });
+    }, "qwikifyQrl_component_zH94hIe0Ick", [
+        reactCmpQrl
+    ]), {
+        tagName: 'qwik-wrap'
+    });
+}
+const filterProps = (props)=>{
+    const obj = {};
+    Object.keys(props).forEach((key)=>{
+        if (!key.startsWith('client:')) obj[key] = props[key];
+    });
+    return obj;
+};
+const qwikify$ = implicit$FirstArg(qwikifyQrl);
+
+async function renderToString(rootNode, opts) {
+    const mod = await import('./server-9ac6caad.js');
+    const result = await mod.renderToString(rootNode, opts);
+    const styles = mod.getGlobalStyleTag(result.html);
+    const finalHtml = styles + result.html;
+    return {
+        ...result,
+        html: finalHtml
+    };
+}
+
+export { qwikify$, qwikifyQrl, renderToString };
+        "#
+        .to_string(),
+        filename: "../node_modules/@builder.io/qwik-react/index.qwik.mjs".to_string(),
+        entry_strategy: EntryStrategy::Hook,
+        explicit_extensions: true,
+        ..TestInput::default()
+    });
+}
+
+#[test]
+fn example_qwik_react_inline() {
+	test_input!(TestInput {
+        code: r#"
+import { componentQrl, inlinedQrl, useLexicalScope, useHostElement, useStore, useTaskQrl, noSerialize, SkipRerender, implicit$FirstArg } from '@builder.io/qwik';
+import { jsx, Fragment } from '@builder.io/qwik/jsx-runtime';
+import { isBrowser, isServer } from '@builder.io/qwik/build';
+
+function qwikifyQrl(reactCmpQrl) {
+    return /*#__PURE__*/ componentQrl(inlinedQrl((props)=>{
+        const [reactCmpQrl] = useLexicalScope();
+        const hostElement = useHostElement();
+        const store = useStore({});
+        let run;
+        if (props['client:visible']) run = 'visible';
         else if (props['client:load'] || props['client:only']) run = 'load';
         useTaskQrl(inlinedQrl(async (track)=>{
             const [hostElement, props, reactCmpQrl, store] = useLexicalScope();
@@ -3083,456 +3907,1300 @@ const filterProps = (props)=>{
 };
 const qwikify$ = implicit$FirstArg(qwikifyQrl);
 
-async function renderToString(rootNode, opts) {
-    const mod = await import('./server-9ac6caad.js');
-    const result = await mod.renderToString(rootNode, opts);
-    const styles = mod.getGlobalStyleTag(result.html);
-    const finalHtml = styles + result.html;
-    return {
-        ...result,
-        html: finalHtml
-    };
+async function renderToString(rootNode, opts) {
+    const mod = await import('./server-9ac6caad.js');
+    const result = await mod.renderToString(rootNode, opts);
+    const styles = mod.getGlobalStyleTag(result.html);
+    const finalHtml = styles + result.html;
+    return {
+        ...result,
+        html: finalHtml
+    };
+}
+
+export { qwikify$, qwikifyQrl, renderToString };
+        "#
+        .to_string(),
+        filename: "../node_modules/@builder.io/qwik-react/index.qwik.mjs".to_string(),
+        entry_strategy: EntryStrategy::Inline,
+        explicit_extensions: true,
+        ..TestInput::default()
+    });
+}
+
+#[test]
+fn example_qwik_sdk_inline() {
+	test_input!(TestInput {
+		code: include_str!("fixtures/index.qwik.mjs").to_string(),
+		filename: "../node_modules/@builder.io/qwik-city/index.qwik.mjs".to_string(),
+		entry_strategy: EntryStrategy::Smart,
+		explicit_extensions: true,
+		mode: EmitMode::Prod,
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn relative_paths() {
+	let dep = r#"
+import { componentQrl, inlinedQrl, useStore, useLexicalScope } from "@builder.io/qwik";
+import { jsx, jsxs } from "@builder.io/qwik/jsx-runtime";
+import { state } from './sibling';
+
+const useData = () => {
+    return useStore({
+        count: 0
+    });
+}
+
+export const App = /*#__PURE__*/ componentQrl(inlinedQrl(()=>{
+    const store = useData();
+    return /*#__PURE__*/ jsxs("div", {
+        children: [
+            /*#__PURE__*/ jsxs("p", {
+                children: [
+                    "Count: ",
+                    store.count
+                ]
+            }),
+            /*#__PURE__*/ jsx("p", {
+                children: /*#__PURE__*/ jsx("button", {
+                    onClick$: inlinedQrl(()=>{
+                        const [store] = useLexicalScope();
+                        return store.count++;
+                    }, "App_component_div_p_button_onClick_8dWUa0cJAr4", [
+                        store
+                    ]),
+                    children: "Click"
+                })
+            })
+        ]
+    });
+}, "App_component_AkbU84a8zes"));
+
+"#;
+	let code = r#"
+import { component$, $ } from '@builder.io/qwik';
+import { state } from './sibling';
+
+export const Local = component$(() => {
+    return (
+        <div>{state}</div>
+    )
+});
+"#;
+	let res = transform_modules(TransformModulesOptions {
+		src_dir: "/path/to/app/src/thing".into(),
+		root_dir: Some("/path/to/app/".into()),
+		input: vec![
+			TransformModuleInput {
+				code: dep.into(),
+				path: "../../node_modules/dep/dist/lib.mjs".into(),
+				map: None,
+			},
+			TransformModuleInput {
+				code: code.into(),
+				path: "components/main.tsx".into(),
+				map: None,
+			},
+		],
+		source_maps: true,
+		inline_source_maps: false,
+		sources_content: false,
+		minify: MinifyMode::Simplify,
+		explicit_extensions: true,
+		mode: EmitMode::Lib,
+		manual_chunks: None,
+		sized_chunks: None,
+		symbol_traces: None,
+		entry_rules: None,
+		es_target: None,
+		jsx_import_source: None,
+		jsx_pragma: None,
+		jsx_pragma_frag: None,
+		define: None,
+		entry_strategy: EntryStrategy::Hook,
+		transpile_ts: true,
+		transpile_jsx: true,
+		preserve_filenames: false,
+		core_module: None,
+		scope: None,
+		strip_exports: None,
+		strip_ctx_name: None,
+		strip_event_handlers: false,
+		reg_ctx_name: None,
+		is_server: None,
+	});
+	snapshot_res!(&res, "".into());
+}
+#[test]
+fn consistent_hashes() {
+	let code = r#"
+import { component$, $ } from '@builder.io/qwik';
+import mongo from 'mongodb';
+
+export const Greeter = component$(() => {
+    // Double count watch
+    useTask$(async () => {
+        await mongo.users();
+    });
+    return (
+        <div>
+            <div onClick$={() => {}}/>
+            <div onClick$={() => {}}/>
+            <div onClick$={() => {}}/>
+        </div>
+    )
+});
+
+"#;
+	let options = vec![
+		(EmitMode::Lib, EntryStrategy::Hook, true),
+		(EmitMode::Lib, EntryStrategy::Single, true),
+		(EmitMode::Lib, EntryStrategy::Component, true),
+		// (EmitMode::Lib, EntryStrategy::Inline, true),
+		(EmitMode::Prod, EntryStrategy::Hook, true),
+		(EmitMode::Prod, EntryStrategy::Single, true),
+		(EmitMode::Prod, EntryStrategy::Component, true),
+		// (EmitMode::Prod, EntryStrategy::Inline, true),
+		(EmitMode::Dev, EntryStrategy::Hook, true),
+		(EmitMode::Dev, EntryStrategy::Single, true),
+		(EmitMode::Dev, EntryStrategy::Component, true),
+		// (EmitMode::Dev, EntryStrategy::Inline, true),
+		(EmitMode::Lib, EntryStrategy::Hook, false),
+		(EmitMode::Lib, EntryStrategy::Single, false),
+		(EmitMode::Lib, EntryStrategy::Component, false),
+		// (EmitMode::Lib, EntryStrategy::Inline, false),
+		(EmitMode::Prod, EntryStrategy::Hook, false),
+		(EmitMode::Prod, EntryStrategy::Single, false),
+		(EmitMode::Prod, EntryStrategy::Component, false),
+		// (EmitMode::Prod, EntryStrategy::Inline, false),
+		(EmitMode::Dev, EntryStrategy::Hook, false),
+		(EmitMode::Dev, EntryStrategy::Single, false),
+		(EmitMode::Dev, EntryStrategy::Component, false),
+		// (EmitMode::Dev, EntryStrategy::Inline, false),
+	];
+
+	let res = transform_modules(TransformModulesOptions {
+		src_dir: "./thing".into(),
+		input: vec![
+			TransformModuleInput {
+				code: code.into(),
+				path: "main.tsx".into(),
+				map: None,
+			},
+			TransformModuleInput {
+				code: code.into(),
+				path: "components/main.tsx".into(),
+				map: None,
+			},
+		],
+		source_maps: true,
+		inline_source_maps: false,
+		sources_content: false,
+		minify: MinifyMode::Simplify,
+		root_dir: None,
+		explicit_extensions: true,
+		mode: EmitMode::Lib,
+		manual_chunks: None,
+		sized_chunks: None,
+		symbol_traces: None,
+		entry_rules: None,
+		es_target: None,
+		jsx_import_source: None,
+		jsx_pragma: None,
+		jsx_pragma_frag: None,
+		define: None,
+		entry_strategy: EntryStrategy::Hook,
+		transpile_ts: true,
+		transpile_jsx: true,
+		preserve_filenames: false,
+		scope: None,
+		core_module: None,
+		reg_ctx_name: None,
+		strip_exports: None,
+		strip_ctx_name: None,
+		strip_event_handlers: false,
+		is_server: None,
+	});
+	let ref_hooks: Vec<_> = res
+		.unwrap()
+		.modules
+		.into_iter()
+		.flat_map(|module| module.hook)
+		.collect();
+
+	for (i, option) in options.into_iter().enumerate() {
+		let res = transform_modules(TransformModulesOptions {
+			src_dir: "./thing".into(),
+			input: vec![
+				TransformModuleInput {
+					code: code.into(),
+					path: "main.tsx".into(),
+					map: None,
+				},
+				TransformModuleInput {
+					code: code.into(),
+					path: "components/main.tsx".into(),
+					map: None,
+				},
+			],
+			root_dir: None,
+			source_maps: false,
+			inline_source_maps: false,
+			sources_content: false,
+			minify: MinifyMode::Simplify,
+			explicit_extensions: true,
+			mode: option.0,
+			manual_chunks: None,
+			sized_chunks: None,
+			symbol_traces: None,
+			entry_rules: None,
+			es_target: None,
+			jsx_import_source: None,
+			jsx_pragma: None,
+			jsx_pragma_frag: None,
+			define: None,
+			entry_strategy: option.1,
+			transpile_ts: option.2,
+			transpile_jsx: option.2,
+			preserve_filenames: false,
+			scope: None,
+			core_module: None,
+			strip_exports: None,
+			strip_ctx_name: None,
+			strip_event_handlers: false,
+			reg_ctx_name: None,
+			is_server: None,
+		});
+
+		let hooks: Vec<_> = res
+			.unwrap()
+			.modules
+			.into_iter()
+			.flat_map(|module| module.hook)
+			.collect();
+
+		assert_eq!(hooks.len(), ref_hooks.len());
+
+		for (a, b) in hooks.iter().zip(ref_hooks.iter()) {
+			assert_eq!(
+				get_hash(a.name.as_ref()),
+				get_hash(b.name.as_ref()),
+				"INDEX: {}\n\n{:#?}\n\n{:#?}\n\n{:#?}\n\n{:#?}",
+				i,
+				a,
+				b,
+				hooks,
+				ref_hooks
+			);
+		}
+	}
 }
 
-export { qwikify$, qwikifyQrl, renderToString };
+#[test]
+fn issue_5008() {
+	test_input!(TestInput {
+		code: r#"
+        import { component$, useStore } from "@builder.io/qwik";
+
+        export default component$(() => {
+        const store = useStore([{ value: 0 }]);
+        return (
+            <>
+            <button onClick$={() => store[0].value++}>+1</button>
+            {store.map(function (v, idx) {
+                return <div key={"fn_" + idx}>Function: {v.value}</div>;
+            })}
+            {store.map((v, idx) => (
+                <div key={"arrow_" + idx}>Arrow: {v.value}</div>
+            ))}
+            </>
+        );
+        });
         "#
-        .to_string(),
-        filename: "../node_modules/@builder.io/qwik-react/index.qwik.mjs".to_string(),
-        entry_strategy: EntryStrategy::Hook,
-        explicit_extensions: true,
-        ..TestInput::default()
-    });
+		.to_string(),
+		transpile_ts: true,
+		transpile_jsx: true,
+		..TestInput::default()
+	});
 }
 
 #[test]
-fn example_qwik_react_inline() {
+fn example_of_synchronous_qrl() {
 	test_input!(TestInput {
-        code: r#"
-import { componentQrl, inlinedQrl, useLexicalScope, useHostElement, useStore, useTaskQrl, noSerialize, SkipRerender, implicit$FirstArg } from '@builder.io/qwik';
-import { jsx, Fragment } from '@builder.io/qwik/jsx-runtime';
-import { isBrowser, isServer } from '@builder.io/qwik/build';
+		code: r#"
+        import { sync$, component$ } from "@builder.io/qwik";
 
-function qwikifyQrl(reactCmpQrl) {
-    return /*#__PURE__*/ componentQrl(inlinedQrl((props)=>{
-        const [reactCmpQrl] = useLexicalScope();
-        const hostElement = useHostElement();
-        const store = useStore({});
-        let run;
-        if (props['client:visible']) run = 'visible';
-        else if (props['client:load'] || props['client:only']) run = 'load';
-        useTaskQrl(inlinedQrl(async (track)=>{
-            const [hostElement, props, reactCmpQrl, store] = useLexicalScope();
-            track(props);
-            if (isBrowser) {
-                if (store.data) store.data.root.render(store.data.client.Main(store.data.cmp, filterProps(props)));
-                else {
-                    const [Cmp, client] = await Promise.all([
-                        reactCmpQrl.resolve(),
-                        import('./client-f762f78c.js')
-                    ]);
-                    let root;
-                    if (hostElement.childElementCount > 0) root = client.hydrateRoot(hostElement, client.Main(Cmp, filterProps(props), store.event));
-                    else {
-                        root = client.createRoot(hostElement);
-                        root.render(client.Main(Cmp, filterProps(props)));
-                    }
-                    store.data = noSerialize({
-                        client,
-                        cmp: Cmp,
-                        root
-                    });
-                }
-            }
-        }, "qwikifyQrl_component_useWatch_x04JC5xeP1U", [
-            hostElement,
-            props,
-            reactCmpQrl,
-            store
-        ]), {
-            run
-        });
-        if (isServer && !props['client:only']) {
-            const jsx$1 = Promise.all([
-                reactCmpQrl.resolve(),
-                import('./server-9ac6caad.js')
-            ]).then(([Cmp, server])=>{
-                const html = server.render(Cmp, filterProps(props));
-                return /*#__PURE__*/ jsx(Host, {
-                    dangerouslySetInnerHTML: html,
-                    [_IMMUTABLE]: [
-                        "dangerouslySetInnerHTML"
-                    ]
-                });
-            });
-            return /*#__PURE__*/ jsx(Fragment, {
-                children: jsx$1
-            });
-        }
-        return /*#__PURE__*/ jsx(Host, {
-            children: /*#__PURE__*/ jsx(SkipRerender, {})
+        export default component$(() => {
+        return (
+            <>
+                <input onClick$={sync$(function(event, target) {
+                    // comment should be removed
+                    event.preventDefault();
+                })}/>
+                <input onClick$={sync$((event, target) => {
+                    event.preventDefault();
+                })}/>
+                <input onClick$={sync$((event, target) => event.preventDefault())}/>
+            </>
+        );
         });
-    }, "qwikifyQrl_component_zH94hIe0Ick", [
-        reactCmpQrl
-    ]), {
-        tagName: 'qwik-wrap'
-    });
+        "#
+		.to_string(),
+		transpile_ts: true,
+		transpile_jsx: true,
+		..TestInput::default()
+	});
 }
-const filterProps = (props)=>{
-    const obj = {};
-    Object.keys(props).forEach((key)=>{
-        if (!key.startsWith('client:')) obj[key] = props[key];
-    });
-    return obj;
-};
-const qwikify$ = implicit$FirstArg(qwikifyQrl);
 
-async function renderToString(rootNode, opts) {
-    const mod = await import('./server-9ac6caad.js');
-    const result = await mod.renderToString(rootNode, opts);
-    const styles = mod.getGlobalStyleTag(result.html);
-    const finalHtml = styles + result.html;
-    return {
-        ...result,
-        html: finalHtml
-    };
+#[test]
+fn example_noop_dev_mode() {
+	test_input!(TestInput {
+		code: r#"
+import { component$, useStore, serverStuff$, $ } from '@builder.io/qwik';
+
+export const App = component$(() => {
+    const stuff = useStore();
+    serverStuff$(async () => {
+        // should be removed but keep scope
+        console.log(stuff.count)
+    })
+    serverStuff$(async () => {
+        // should be removed
+    })
+
+    return (
+        <Cmp>
+            <p class="stuff" 
+                shouldRemove$={() => stuff.count}
+                onClick$={() => console.log('warn')}
+            >
+                Hello Qwik
+            </p>
+        </Cmp>
+    );
+});
+"#
+		.to_string(),
+		mode: EmitMode::Dev,
+		transpile_ts: true,
+		transpile_jsx: true,
+		strip_event_handlers: true,
+		strip_ctx_name: Some(vec!["server".into()]),
+		..TestInput::default()
+	});
+}
+
+#[test]
+fn renamed_core_module() {
+	let code = r#"
+import { component$, useSignal, Fragment, RenderOnce } from '@builder.io/qwik';
+import { isServer, isBrowser } from '@builder.io/qwik/build';
+
+export const Counter = component$(() => {
+    const count = useSignal(0);
+    if (isServer) {
+        console.log('server');
+    }
+    return (
+        <Fragment>
+            <RenderOnce>{isBrowser && 'browser'}</RenderOnce>
+            <>
+                <button onClick$={() => count.value++}>+</button>
+                <span>{count.value}</span>
+            </>
+        </Fragment>
+    );
+});
+"#;
+	let transform = |core_module: &str| {
+		transform_modules(TransformModulesOptions {
+			input: vec![TransformModuleInput {
+				code: code.replace("@builder.io/qwik", core_module),
+				path: "test.tsx".into(),
+				map: None,
+			}],
+			mode: EmitMode::Prod,
+			core_module: Some(core_module.into()),
+			is_server: Some(true),
+			..TransformModulesOptions::default()
+		})
+		.unwrap()
+	};
+	let default = transform("@builder.io/qwik");
+	let renamed = transform("@acme/qwik");
+	assert_eq!(default.modules.len(), renamed.modules.len());
+	for (default, renamed) in default.modules.iter().zip(&renamed.modules) {
+		assert_eq!(default.path, renamed.path);
+		assert_eq!(
+			default.code,
+			renamed.code.replace("@acme/qwik", "@builder.io/qwik")
+		);
+	}
+	assert!(renamed
+		.modules
+		.iter()
+		.all(|module| !module.code.contains("isServer")));
+}
+
+#[test]
+fn input_source_map() {
+	let code = r#"
+import { component$ } from '@builder.io/qwik';
+
+export const Doc = component$(() => {
+    return <button onClick$={() => console.log('click')}>Click</button>;
+});
+"#;
+	// Every line of the input comes from 10 lines further down in the original document
+	let mut builder = sourcemap::SourceMapBuilder::new(None);
+	let src_id = builder.add_source("doc.mdx");
+	builder.set_source_contents(src_id, Some("# Doc"));
+	for (line, text) in code.lines().enumerate() {
+		for col in 0..text.len() as u32 {
+			builder.add_raw(line as u32, col, line as u32 + 10, col, Some(src_id), None);
+		}
+	}
+	let mut input_map = vec![];
+	builder.into_sourcemap().to_writer(&mut input_map).unwrap();
+
+	let res = transform_modules(TransformModulesOptions {
+		input: vec![TransformModuleInput {
+			code: code.into(),
+			path: "doc.tsx".into(),
+			map: Some(String::from_utf8(input_map).unwrap()),
+		}],
+		source_maps: true,
+		sources_content: true,
+		mode: EmitMode::Prod,
+		..TransformModulesOptions::default()
+	})
+	.unwrap();
+	assert_eq!(res.modules.len(), 3);
+	for module in &res.modules {
+		let map =
+			sourcemap::SourceMap::from_slice(module.map.as_ref().unwrap().as_bytes()).unwrap();
+		assert_eq!(map.sources().collect::<Vec<_>>(), vec!["doc.mdx"]);
+		assert_eq!(map.get_source_contents(0), Some("# Doc"));
+		assert!(map.get_token_count() > 0);
+		assert!(map.tokens().all(|token| token.get_src_line() >= 10));
+	}
 }
 
-export { qwikify$, qwikifyQrl, renderToString };
-        "#
-        .to_string(),
-        filename: "../node_modules/@builder.io/qwik-react/index.qwik.mjs".to_string(),
-        entry_strategy: EntryStrategy::Inline,
-        explicit_extensions: true,
-        ..TestInput::default()
-    });
+#[test]
+fn input_source_map_with_gaps() {
+	let code = r#"
+import { component$ } from '@builder.io/qwik';
+
+export const Doc = component$(() => {
+    return <button onClick$={() => console.log('click')}>Click</button>;
+});
+"#;
+	// Only the import line comes from the original document
+	let mut builder = sourcemap::SourceMapBuilder::new(None);
+	let src_id = builder.add_source("doc.mdx");
+	let import_line = code.lines().nth(1).unwrap();
+	for col in 0..import_line.len() as u32 {
+		builder.add_raw(1, col, 10, col, Some(src_id), None);
+	}
+	let mut input_map = vec![];
+	builder.into_sourcemap().to_writer(&mut input_map).unwrap();
+
+	let res = transform_modules(TransformModulesOptions {
+		input: vec![TransformModuleInput {
+			code: code.into(),
+			path: "doc.tsx".into(),
+			map: Some(String::from_utf8(input_map).unwrap()),
+		}],
+		source_maps: true,
+		mode: EmitMode::Prod,
+		..TransformModulesOptions::default()
+	})
+	.unwrap();
+	let hooks: Vec<_> = res
+		.modules
+		.iter()
+		.filter(|module| module.hook.is_some())
+		.collect();
+	assert_eq!(hooks.len(), 2);
+	for module in hooks {
+		// The hooks come from lines the input map does not cover
+		let map =
+			sourcemap::SourceMap::from_slice(module.map.as_ref().unwrap().as_bytes()).unwrap();
+		assert_eq!(map.get_token_count(), 0);
+	}
 }
 
 #[test]
-fn example_qwik_sdk_inline() {
-	test_input!(TestInput {
-		code: include_str!("fixtures/index.qwik.mjs").to_string(),
-		filename: "../node_modules/@builder.io/qwik-city/index.qwik.mjs".to_string(),
-		entry_strategy: EntryStrategy::Smart,
-		explicit_extensions: true,
+fn input_source_map_invalid() {
+	let res = transform_modules(TransformModulesOptions {
+		input: vec![TransformModuleInput {
+			code: "export const a = 1;".into(),
+			path: "index.js".into(),
+			map: Some("{".into()),
+		}],
+		source_maps: true,
 		mode: EmitMode::Prod,
-		..TestInput::default()
+		..TransformModulesOptions::default()
 	});
+	let err = res.unwrap_err();
+	assert_eq!(err.code(), "ERR_INVALID_SOURCE_MAP");
+	assert_eq!(err.path(), Some(Path::new("index.js")));
 }
 
 #[test]
-fn relative_paths() {
-	let dep = r#"
-import { componentQrl, inlinedQrl, useStore, useLexicalScope } from "@builder.io/qwik";
-import { jsx, jsxs } from "@builder.io/qwik/jsx-runtime";
-import { state } from './sibling';
+fn inline_source_maps() {
+	let code = r#"
+import { component$ } from '@builder.io/qwik';
 
-const useData = () => {
-    return useStore({
-        count: 0
-    });
+export const App = component$(() => {
+    return <button onClick$={() => console.log('click')}>Click</button>;
+});
+"#;
+	let res = transform_modules(TransformModulesOptions {
+		input: vec![TransformModuleInput {
+			code: code.into(),
+			path: "app.tsx".into(),
+			map: None,
+		}],
+		source_maps: true,
+		inline_source_maps: true,
+		sources_content: true,
+		mode: EmitMode::Prod,
+		..TransformModulesOptions::default()
+	})
+	.unwrap();
+	assert_eq!(res.modules.len(), 3);
+	for module in &res.modules {
+		assert_eq!(module.map, None);
+		let (_, data) = module
+			.code
+			.split_once("\n//# sourceMappingURL=data:application/json;charset=utf-8;base64,")
+			.unwrap();
+		let map = sourcemap::SourceMap::from_slice(
+			&base64::Engine::decode(&base64::engine::general_purpose::STANDARD, data).unwrap(),
+		)
+		.unwrap();
+		assert_eq!(map.get_source_contents(0), Some(code));
+	}
+	assert_eq!(
+		res.modules[0].source_map_file("[file].map"),
+		format!("{}.map", res.modules[0].path)
+	);
 }
 
-export const App = /*#__PURE__*/ componentQrl(inlinedQrl(()=>{
-    const store = useData();
-    return /*#__PURE__*/ jsxs("div", {
-        children: [
-            /*#__PURE__*/ jsxs("p", {
-                children: [
-                    "Count: ",
-                    store.count
-                ]
-            }),
-            /*#__PURE__*/ jsx("p", {
-                children: /*#__PURE__*/ jsx("button", {
-                    onClick$: inlinedQrl(()=>{
-                        const [store] = useLexicalScope();
-                        return store.count++;
-                    }, "App_component_div_p_button_onClick_8dWUa0cJAr4", [
-                        store
-                    ]),
-                    children: "Click"
-                })
-            })
-        ]
-    });
-}, "App_component_AkbU84a8zes"));
-
-"#;
+#[test]
+fn transform_cache_hits() {
 	let code = r#"
 import { component$, $ } from '@builder.io/qwik';
-import { state } from './sibling';
 
-export const Local = component$(() => {
+export const Greeter = component$(() => {
     return (
-        <div>{state}</div>
+        <div onClick$={() => console.log('hello')}/>
     )
 });
 "#;
-	let res = transform_modules(TransformModulesOptions {
-		src_dir: "/path/to/app/src/thing".into(),
-		root_dir: Some("/path/to/app/".into()),
+	let options = |code: &str| TransformModulesOptions {
 		input: vec![
 			TransformModuleInput {
-				code: dep.into(),
-				path: "../../node_modules/dep/dist/lib.mjs".into(),
+				code: code.into(),
+				path: "main.tsx".into(),
+				map: None,
 			},
 			TransformModuleInput {
-				code: code.into(),
-				path: "components/main.tsx".into(),
+				code: "export const a = 1;".into(),
+				path: "other.tsx".into(),
+				map: None,
 			},
 		],
-		source_maps: true,
-		minify: MinifyMode::Simplify,
-		explicit_extensions: true,
-		mode: EmitMode::Lib,
-		manual_chunks: None,
-		entry_strategy: EntryStrategy::Hook,
-		transpile_ts: true,
-		transpile_jsx: true,
-		preserve_filenames: false,
-		core_module: None,
-		scope: None,
-		strip_exports: None,
-		strip_ctx_name: None,
-		strip_event_handlers: false,
-		reg_ctx_name: None,
-		is_server: None,
+		entry_strategy: EntryStrategy::Component,
+		..TransformModulesOptions::default()
+	};
+	let cache = TransformCache::new();
+	let uncached = transform_modules(options(code)).unwrap();
+	let first = transform_modules_with_cache(options(code), &cache).unwrap();
+	assert_eq!(cache.stats(), CacheStats { hits: 0, misses: 2 });
+
+	let second = transform_modules_with_cache(options(code), &cache).unwrap();
+	assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 2 });
+	for output in [&first, &second] {
+		let paths: Vec<_> = output.modules.iter().map(|m| &m.path).collect();
+		let expected: Vec<_> = uncached.modules.iter().map(|m| &m.path).collect();
+		assert_eq!(paths, expected);
+		// Entry modules are still generated from cached hooks
+		assert!(output
+			.modules
+			.iter()
+			.any(|m| m.is_entry && m.hook.is_none()));
+	}
+
+	let changed = code.replace("hello", "bye");
+	transform_modules_with_cache(options(&changed), &cache).unwrap();
+	assert_eq!(cache.stats(), CacheStats { hits: 3, misses: 3 });
+
+	// The changed module replaced the previous entry of its path
+	transform_modules_with_cache(options(code), &cache).unwrap();
+	assert_eq!(cache.stats(), CacheStats { hits: 4, misses: 4 });
+
+	let mut other_options = options(code);
+	other_options.mode = EmitMode::Prod;
+	transform_modules_with_cache(other_options, &cache).unwrap();
+	assert_eq!(cache.stats(), CacheStats { hits: 4, misses: 6 });
+}
+
+#[cfg(feature = "fs")]
+#[test]
+fn transform_cache_persists_to_disk() {
+	let dir = std::env::temp_dir().join(format!("qwik-cache-test-{}", std::process::id()));
+	let options = || TransformModulesOptions {
+		input: vec![TransformModuleInput {
+			code: "import { $ } from '@builder.io/qwik';\nexport const a = $(() => 1);".into(),
+			path: "main.tsx".into(),
+			map: None,
+		}],
+		..TransformModulesOptions::default()
+	};
+	let first = {
+		let cache = TransformCache::with_dir(&dir).unwrap();
+		let output = transform_modules_with_cache(options(), &cache).unwrap();
+		assert_eq!(cache.stats(), CacheStats { hits: 0, misses: 1 });
+		output
+	};
+	let cache = TransformCache::with_dir(&dir).unwrap();
+	let second = transform_modules_with_cache(options(), &cache).unwrap();
+	assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 0 });
+	std::fs::remove_dir_all(&dir).unwrap();
+
+	assert_eq!(first.modules.len(), second.modules.len());
+	for (a, b) in first.modules.iter().zip(second.modules.iter()) {
+		assert_eq!(a.path, b.path);
+		assert_eq!(a.code, b.code);
+		assert_eq!(a.order, b.order);
+		assert_eq!(a.hook, b.hook);
+	}
+}
+
+#[cfg(feature = "fs")]
+#[test]
+fn transform_cache_disk_errors() {
+	let dir = std::env::temp_dir().join(format!("qwik-cache-errors-{}", std::process::id()));
+	let options = || TransformModulesOptions {
+		input: vec![TransformModuleInput {
+			code: "import { $ } from '@builder.io/qwik';\nexport const a = $(() => 1);".into(),
+			path: "main.tsx".into(),
+			map: None,
+		}],
+		..TransformModulesOptions::default()
+	};
+	{
+		let cache = TransformCache::with_dir(&dir).unwrap();
+		transform_modules_with_cache(options(), &cache).unwrap();
+	}
+	// A corrupted entry is transformed again
+	for entry in std::fs::read_dir(&dir).unwrap() {
+		std::fs::write(entry.unwrap().path(), "{").unwrap();
+	}
+	let cache = TransformCache::with_dir(&dir).unwrap();
+	transform_modules_with_cache(options(), &cache).unwrap();
+	assert_eq!(cache.stats(), CacheStats { hits: 0, misses: 1 });
+
+	// An entry that can not be written is an error
+	let cache = TransformCache::with_dir(&dir).unwrap();
+	std::fs::remove_dir_all(&dir).unwrap();
+	let err = transform_modules_with_cache(options(), &cache).unwrap_err();
+	assert_eq!(err.code(), "ERR_IO");
+}
+
+#[cfg(feature = "fs")]
+#[test]
+fn transform_fs_glob() {
+	let src_dir = std::env::temp_dir().join(format!("qwik-glob-test-{}", std::process::id()));
+	let files = [
+		"app.tsx",
+		"app.test.tsx",
+		"components/button.tsx",
+		"components/button.test.tsx",
+		"stories/button.stories.tsx",
+		"fixtures/data.ts",
+	];
+	for file in files {
+		let path = src_dir.join(file);
+		std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+		std::fs::write(path, "export const a = 1;").unwrap();
+	}
+	let res = transform_fs(TransformFsOptions {
+		src_dir: src_dir.to_string_lossy().to_string(),
+		glob: Some(Globs::Many(vec![
+			"**/*.tsx".into(),
+			"!**/*.test.tsx".into(),
+			"!stories/**".into(),
+		])),
+		preserve_filenames: true,
+		..TransformFsOptions::default()
 	});
-	snapshot_res!(&res, "".into());
+	std::fs::remove_dir_all(&src_dir).unwrap();
+
+	let mut paths: Vec<_> = res.unwrap().modules.into_iter().map(|m| m.path).collect();
+	paths.sort();
+	assert_eq!(paths, vec!["app.tsx", "components/button.tsx"]);
 }
+
+#[cfg(feature = "fs")]
 #[test]
-fn consistent_hashes() {
-	let code = r#"
-import { component$, $ } from '@builder.io/qwik';
-import mongo from 'mongodb';
+fn transform_fs_options_glob_string_or_array() {
+	let options = |glob: &str| -> TransformFsOptions {
+		serde_json::from_str(&format!(
+			r#"{{"srcDir": "/src", "vendorRoots": [], "glob": {glob}, "minify": "simplify",
+			"entryStrategy": "hook", "sourceMaps": false, "inlineSourceMaps": false,
+			"sourcesContent": false, "transpileTs": true, "transpileJsx": true,
+			"preserveFilenames": false, "explicitExtensions": false, "mode": "lib",
+			"stripEventHandlers": false}}"#
+		))
+		.unwrap()
+	};
+	let one = options(r#""**/*.tsx""#).glob.unwrap();
+	assert_eq!(one.as_slice(), ["**/*.tsx"]);
+	let many = options(r#"["**/*.tsx", "!**/*.test.tsx"]"#).glob.unwrap();
+	assert_eq!(many.as_slice(), ["**/*.tsx", "!**/*.test.tsx"]);
+}
 
-export const Greeter = component$(() => {
-    // Double count watch
-    useTask$(async () => {
-        await mongo.users();
-    });
-    return (
-        <div>
-            <div onClick$={() => {}}/>
-            <div onClick$={() => {}}/>
-            <div onClick$={() => {}}/>
-        </div>
-    )
+#[cfg(feature = "fs")]
+#[test]
+fn transform_fs_streaming_outputs() {
+	let src_dir = std::env::temp_dir().join(format!("qwik-streaming-test-{}", std::process::id()));
+	let files = [
+		(
+			"app.tsx",
+			r#"
+import { component$ } from '@builder.io/qwik';
+import { Button } from './components/button';
+
+export const App = component$(() => {
+    return <Button />;
 });
+"#,
+		),
+		(
+			"components/button.tsx",
+			r#"
+import { component$ } from '@builder.io/qwik';
 
-"#;
-	let options = vec![
-		(EmitMode::Lib, EntryStrategy::Hook, true),
-		(EmitMode::Lib, EntryStrategy::Single, true),
-		(EmitMode::Lib, EntryStrategy::Component, true),
-		// (EmitMode::Lib, EntryStrategy::Inline, true),
-		(EmitMode::Prod, EntryStrategy::Hook, true),
-		(EmitMode::Prod, EntryStrategy::Single, true),
-		(EmitMode::Prod, EntryStrategy::Component, true),
-		// (EmitMode::Prod, EntryStrategy::Inline, true),
-		(EmitMode::Dev, EntryStrategy::Hook, true),
-		(EmitMode::Dev, EntryStrategy::Single, true),
-		(EmitMode::Dev, EntryStrategy::Component, true),
-		// (EmitMode::Dev, EntryStrategy::Inline, true),
-		(EmitMode::Lib, EntryStrategy::Hook, false),
-		(EmitMode::Lib, EntryStrategy::Single, false),
-		(EmitMode::Lib, EntryStrategy::Component, false),
-		// (EmitMode::Lib, EntryStrategy::Inline, false),
-		(EmitMode::Prod, EntryStrategy::Hook, false),
-		(EmitMode::Prod, EntryStrategy::Single, false),
-		(EmitMode::Prod, EntryStrategy::Component, false),
-		// (EmitMode::Prod, EntryStrategy::Inline, false),
-		(EmitMode::Dev, EntryStrategy::Hook, false),
-		(EmitMode::Dev, EntryStrategy::Single, false),
-		(EmitMode::Dev, EntryStrategy::Component, false),
-		// (EmitMode::Dev, EntryStrategy::Inline, false),
+export const Button = component$(() => {
+    return <button onClick$={() => console.log('click')}>Click</button>;
+});
+"#,
+		),
 	];
+	for (file, code) in files {
+		let path = src_dir.join(file);
+		std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+		std::fs::write(path, code).unwrap();
+	}
+	let options = || TransformFsOptions {
+		src_dir: src_dir.to_string_lossy().to_string(),
+		mode: EmitMode::Prod,
+		entry_strategy: EntryStrategy::Single,
+		..TransformFsOptions::default()
+	};
+	let expected = transform_fs(options()).unwrap();
+	let streamed = std::sync::Mutex::new(vec![]);
+	let res = transform_fs_streaming(options(), &|output| {
+		streamed.lock().unwrap().push(output);
+		Ok(())
+	});
+	// A failing consumer stops the transform, the entries are never emitted
+	let calls = std::sync::atomic::AtomicUsize::new(0);
+	let aborted = transform_fs_streaming(options(), &|_| {
+		calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+		Err(TransformError::aborted("consumer failed"))
+	});
+	std::fs::remove_dir_all(&src_dir).unwrap();
+	let res = res.unwrap();
+	assert_eq!(aborted.unwrap_err().code(), "ERR_ABORTED");
+	assert!(calls.into_inner() <= 2);
+
+	// One output per file, then the entries
+	let streamed = streamed.into_inner().unwrap();
+	assert_eq!(streamed.len(), 3);
+	assert!(streamed[2].modules.iter().all(|module| module.is_entry));
+
+	let mut modules: Vec<_> = streamed
+		.into_iter()
+		.flat_map(|output| output.modules)
+		.collect();
+	modules.sort_by(|a, b| a.path.cmp(&b.path));
+	let mut expected_modules = expected.modules.clone();
+	expected_modules.sort_by(|a, b| a.path.cmp(&b.path));
+	assert_eq!(modules.len(), expected_modules.len());
+	for (a, b) in modules.iter().zip(&expected_modules) {
+		assert_eq!(a.path, b.path);
+		assert_eq!(a.code, b.code);
+	}
+
+	assert!(res.modules.iter().all(|module| module.code.is_empty()));
+	assert_eq!(
+		serde_json::to_value(res.get_manifest()).unwrap(),
+		serde_json::to_value(expected.get_manifest()).unwrap()
+	);
+}
 
+#[test]
+fn transform_error_invalid_path() {
 	let res = transform_modules(TransformModulesOptions {
-		src_dir: "./thing".into(),
-		input: vec![
-			TransformModuleInput {
-				code: code.into(),
-				path: "main.tsx".into(),
-			},
-			TransformModuleInput {
-				code: code.into(),
-				path: "components/main.tsx".into(),
-			},
-		],
+		input: vec![TransformModuleInput {
+			code: "export const a = 1;".into(),
+			path: "components/README".into(),
+			map: None,
+		}],
+		..TransformModulesOptions::default()
+	});
+	let err = res.unwrap_err();
+	assert!(matches!(err, TransformError::InvalidPath { .. }));
+	assert_eq!(err.code(), "ERR_INVALID_PATH");
+	assert_eq!(err.path(), Some(std::path::Path::new("components/README")));
+}
+
+#[cfg(feature = "fs")]
+#[test]
+fn transform_error_io() {
+	let src_dir = std::env::temp_dir().join(format!("qwik-io-error-test-{}", std::process::id()));
+	let file = src_dir.join("app.tsx");
+	std::fs::create_dir_all(&file).unwrap();
+	// A directory named like a module can not be read as a file
+	let res = TransformOutput {
+		modules: vec![TransformModule {
+			path: "app.tsx".into(),
+			..TransformModule::default()
+		}],
+		..TransformOutput::default()
+	}
+	.write_to_fs(&src_dir, None, None);
+	std::fs::remove_dir_all(&src_dir).unwrap();
+
+	let err = res.unwrap_err();
+	assert_eq!(err.code(), "ERR_IO");
+	assert_eq!(err.path(), Some(file.as_path()));
+}
+
+#[cfg(feature = "fs")]
+#[test]
+fn write_to_fs_source_maps() {
+	let code = r#"
+import { component$ } from '@builder.io/qwik';
+
+export const App = component$(() => {
+    return <button onClick$={() => console.log('click')}>Click</button>;
+});
+"#;
+	let res = transform_modules(TransformModulesOptions {
+		root_dir: Some("/user/qwik".into()),
+		input: vec![TransformModuleInput {
+			code: code.into(),
+			path: "components/app.tsx".into(),
+			map: None,
+		}],
 		source_maps: true,
-		minify: MinifyMode::Simplify,
-		root_dir: None,
-		explicit_extensions: true,
-		mode: EmitMode::Lib,
-		manual_chunks: None,
-		entry_strategy: EntryStrategy::Hook,
-		transpile_ts: true,
-		transpile_jsx: true,
-		preserve_filenames: false,
+		mode: EmitMode::Prod,
+		entry_strategy: EntryStrategy::Single,
+		..TransformModulesOptions::default()
+	})
+	.unwrap();
+	let dest = std::env::temp_dir().join(format!("qwik-source-maps-test-{}", std::process::id()));
+	res.write_to_fs(&dest, None, Some("[file].map")).unwrap();
+
+	let mut entries = 0;
+	for module in &res.modules {
+		let module_path = dest.join(&module.path);
+		let map_file = format!("{}.map", module_path.file_name().unwrap().to_string_lossy());
+		let written = std::fs::read_to_string(&module_path).unwrap();
+		assert!(written.ends_with(&format!("\n//# sourceMappingURL={}\n", map_file)));
+
+		let map = std::fs::read(module_path.with_file_name(&map_file)).unwrap();
+		let map = sourcemap::SourceMap::from_slice(&map).unwrap();
+		assert_eq!(map.get_source_root(), None);
+		assert_eq!(
+			map.get_file(),
+			Some(module_path.file_name().unwrap().to_str().unwrap())
+		);
+		if module.is_entry && module.hook.is_none() {
+			entries += 1;
+			assert_eq!(map.get_source_count(), 0);
+			continue;
+		}
+		let expected = pathdiff::diff_paths(
+			"/user/qwik/src/components/app.tsx",
+			module_path.parent().unwrap(),
+		)
+		.unwrap();
+		assert_eq!(
+			map.sources().collect::<Vec<_>>(),
+			vec![expected.to_str().unwrap()]
+		);
+	}
+	std::fs::remove_dir_all(&dest).unwrap();
+	assert_eq!(entries, 1);
+}
+
+#[test]
+fn analyze_modules_hooks_and_captures() {
+	let code = r#"
+import { component$, useSignal, $ } from '@builder.io/qwik';
+
+export const Counter = component$(() => {
+    const count = useSignal(0);
+    const step = useSignal(2);
+    return (
+        <button onClick$={() => count.value += step.value}>{count.value}</button>
+    );
+});
+"#;
+	let input = || {
+		vec![TransformModuleInput {
+			code: code.into(),
+			path: "counter.tsx".into(),
+			map: None,
+		}]
+	};
+	let analysis = analyze_modules(AnalyzeModulesOptions {
+		src_dir: "/user/qwik/src/".into(),
+		root_dir: None,
+		input: input(),
+		mode: EmitMode::Lib,
 		scope: None,
 		core_module: None,
-		reg_ctx_name: None,
-		strip_exports: None,
-		strip_ctx_name: None,
-		strip_event_handlers: false,
 		is_server: None,
-	});
-	let ref_hooks: Vec<_> = res
-		.unwrap()
+		jsx_import_source: None,
+		jsx_pragma: None,
+		jsx_pragma_frag: None,
+		define: None,
+	})
+	.unwrap();
+	let transformed = transform_modules(TransformModulesOptions {
+		input: input(),
+		..TransformModulesOptions::default()
+	})
+	.unwrap();
+
+	assert!(analysis.diagnostics.is_empty());
+	assert_eq!(analysis.modules.len(), 1);
+	let module = &analysis.modules[0];
+	assert_eq!(module.path, JsWord::from("counter.tsx"));
+
+	let mut hooks: Vec<_> = module.hooks.iter().map(|h| &h.hook).collect();
+	hooks.sort_by_key(|hook| &hook.name);
+	let mut expected: Vec<_> = transformed
 		.modules
-		.into_iter()
-		.flat_map(|module| module.hook)
+		.iter()
+		.filter_map(|m| m.hook.as_ref())
 		.collect();
+	expected.sort_by_key(|hook| &hook.name);
+	assert_eq!(hooks, expected);
+
+	let click = module
+		.hooks
+		.iter()
+		.find(|h| h.hook.ctx_name == *"onClick$")
+		.unwrap();
+	let captured: Vec<_> = click
+		.captured
+		.iter()
+		.map(|ident| {
+			let loc = serde_json::to_value(&ident.loc).unwrap();
+			(ident.name.to_string(), loc["startLine"].clone())
+		})
+		.collect();
+	assert_eq!(
+		captured,
+		vec![
+			("count".to_string(), serde_json::json!(5)),
+			("step".to_string(), serde_json::json!(6)),
+		]
+	);
+}
 
-	for (i, option) in options.into_iter().enumerate() {
-		let res = transform_modules(TransformModulesOptions {
-			src_dir: "./thing".into(),
-			input: vec![
-				TransformModuleInput {
-					code: code.into(),
-					path: "main.tsx".into(),
-				},
-				TransformModuleInput {
-					code: code.into(),
-					path: "components/main.tsx".into(),
-				},
-			],
-			root_dir: None,
-			source_maps: false,
-			minify: MinifyMode::Simplify,
-			explicit_extensions: true,
-			mode: option.0,
-			manual_chunks: None,
-			entry_strategy: option.1,
-			transpile_ts: option.2,
-			transpile_jsx: option.2,
-			preserve_filenames: false,
-			scope: None,
-			core_module: None,
-			strip_exports: None,
-			strip_ctx_name: None,
-			strip_event_handlers: false,
-			reg_ctx_name: None,
-			is_server: None,
-		});
-
-		let hooks: Vec<_> = res
-			.unwrap()
-			.modules
-			.into_iter()
-			.flat_map(|module| module.hook)
-			.collect();
+#[test]
+fn manifest_bundles_entries() {
+	let transformed = transform_modules(TransformModulesOptions {
+		input: vec![
+			TransformModuleInput {
+				code: r#"
+import { component$ } from '@builder.io/qwik';
+import { Button } from './button';
 
-		assert_eq!(hooks.len(), ref_hooks.len());
+export const App = component$(() => {
+    return <Button />;
+});
+"#
+				.into(),
+				path: "app.tsx".into(),
+				map: None,
+			},
+			TransformModuleInput {
+				code: r#"
+import { component$ } from '@builder.io/qwik';
 
-		for (a, b) in hooks.iter().zip(ref_hooks.iter()) {
-			assert_eq!(
-				get_hash(a.name.as_ref()),
-				get_hash(b.name.as_ref()),
-				"INDEX: {}\n\n{:#?}\n\n{:#?}\n\n{:#?}\n\n{:#?}",
-				i,
-				a,
-				b,
-				hooks,
-				ref_hooks
-			);
-		}
+export const Button = component$(() => {
+    return <button onClick$={() => console.log('click')}>Click</button>;
+});
+"#
+				.into(),
+				path: "button.tsx".into(),
+				map: None,
+			},
+		],
+		entry_strategy: EntryStrategy::Single,
+		..TransformModulesOptions::default()
+	})
+	.unwrap();
+	let manifest = transformed.get_manifest();
+
+	let entry = &manifest.bundles[&JsWord::from("entry_hooks.js")];
+	assert_eq!(entry.symbols.len(), 3);
+	assert_eq!(
+		entry.origins,
+		vec![JsWord::from("app.tsx"), JsWord::from("button.tsx")]
+	);
+	// `App` renders `Button`, its QRLs are in the entry itself
+	assert_eq!(entry.imports, vec![JsWord::from("button.js")]);
+	assert!(entry.dynamic_imports.is_empty());
+	for symbol in &entry.symbols {
+		assert_eq!(manifest.mapping[symbol], JsWord::from("entry_hooks.js"));
 	}
+
+	let app = &manifest.bundles[&JsWord::from("app.js")];
+	assert!(app.symbols.is_empty());
+	assert!(app.imports.is_empty());
+	assert_eq!(app.dynamic_imports, vec![JsWord::from("entry_hooks.js")]);
+	assert_eq!(app.origins, vec![JsWord::from("app.tsx")]);
+
+	let click = manifest
+		.symbols
+		.values()
+		.find(|symbol| symbol.ctx_name == *"onClick$")
+		.unwrap();
+	let parent = click.parent.as_ref().unwrap();
+	assert_eq!(
+		manifest.symbols[parent].ctx_name,
+		JsWord::from("component$")
+	);
 }
 
 #[test]
-fn issue_5008() {
-	test_input!(TestInput {
-		code: r#"
-        import { component$, useStore } from "@builder.io/qwik";
+fn stable_hash_pinned_outputs() {
+	use crate::hash::StableHasher;
+	use std::hash::Hasher;
+
+	// Changing any of these values renames every symbol, bump `HASH_VERSION` when doing so
+	assert_eq!(HASH_VERSION, 1);
+	for (input, expected) in [
+		("", 15130871412783076140),
+		("qwik", 5977285086133487135),
+		("test.tsx", 12604092606497792187),
+	] {
+		let mut hasher = StableHasher::new();
+		hasher.write(input.as_bytes());
+		assert_eq!(hasher.finish(), expected, "hash of {:?}", input);
+	}
 
-        export default component$(() => {
-        const store = useStore([{ value: 0 }]);
-        return (
-            <>
-            <button onClick$={() => store[0].value++}>+1</button>
-            {store.map(function (v, idx) {
-                return <div key={"fn_" + idx}>Function: {v.value}</div>;
-            })}
-            {store.map((v, idx) => (
-                <div key={"arrow_" + idx}>Arrow: {v.value}</div>
-            ))}
-            </>
-        );
-        });
-        "#
-		.to_string(),
-		transpile_ts: true,
-		transpile_jsx: true,
-		..TestInput::default()
-	});
+	let symbols = |mode: EmitMode, scope: Option<String>| {
+		let output = transform_modules(TransformModulesOptions {
+			input: vec![TransformModuleInput {
+				code: r#"
+import { component$ } from '@builder.io/qwik';
+
+export const Counter = component$(() => {
+    return <button onClick$={() => console.log('click')}>Click</button>;
+});
+"#
+				.into(),
+				path: "components/counter.tsx".into(),
+				map: None,
+			}],
+			mode,
+			scope,
+			..TransformModulesOptions::default()
+		})
+		.unwrap();
+		let mut names: Vec<String> = output
+			.modules
+			.iter()
+			.filter_map(|module| module.hook.as_ref())
+			.map(|hook| hook.name.to_string())
+			.collect();
+		names.sort();
+		names
+	};
+	assert_eq!(
+		symbols(EmitMode::Dev, None),
+		vec![
+			"Counter_component_button_onClick_SSMF0rbpPX4",
+			"Counter_component_f8AVqPMGKkE"
+		]
+	);
+	assert_eq!(
+		symbols(EmitMode::Prod, None),
+		vec!["s_SSMF0rbpPX4", "s_f8AVqPMGKkE"]
+	);
+	assert_eq!(
+		symbols(EmitMode::Dev, Some("my-app".into())),
+		vec![
+			"Counter_component_AN08FUkIBiE",
+			"Counter_component_button_onClick_lQhs0eQxmXU"
+		]
+	);
 }
 
 #[test]
-fn example_of_synchronous_qrl() {
-	test_input!(TestInput {
-		code: r#"
-        import { sync$, component$ } from "@builder.io/qwik";
+fn symbol_traces_group_entries() {
+	let load = |hash: &str, time: f64| SymbolLoad {
+		hash: hash.into(),
+		time,
+	};
+	let traces = SymbolTraces {
+		sessions: vec![
+			vec![
+				load("zTmRHlL09Gg", 0.0),
+				load("mSjGM0NCKTA", 10.0),
+				load("CBpnXgTY6FY", 2000.0),
+				load("gzsAFePmakY", 2050.0),
+			],
+			vec![
+				load("zTmRHlL09Gg", 0.0),
+				load("mSjGM0NCKTA", 20.0),
+				load("CBpnXgTY6FY", 500.0),
+				load("gzsAFePmakY", 560.0),
+				load("CBpnXgTY6FY", 580.0),
+				load("iB2Af6JwrPc", 3000.0),
+			],
+		],
+		window: None,
+	};
+	let output = transform_modules(TransformModulesOptions {
+		input: vec![TransformModuleInput {
+			code: r#"
+import { component$, useStore } from '@builder.io/qwik';
 
-        export default component$(() => {
-        return (
-            <>
-                <input onClick$={sync$(function(event, target) {
-                    // comment should be removed
-                    event.preventDefault();
-                })}/>
-                <input onClick$={sync$((event, target) => {
-                    event.preventDefault();
-                })}/>
-                <input onClick$={sync$((event, target) => event.preventDefault())}/>
-            </>
-        );
-        });
-        "#
-		.to_string(),
-		transpile_ts: true,
-		transpile_jsx: true,
-		..TestInput::default()
-	});
+export const Counter = component$(() => {
+    const state = useStore({ count: 0 });
+    return (
+        <div>
+            <button onClick$={() => state.count++}>+</button>
+            <button onClick$={() => state.count--}>-</button>
+            <span onMouseOver$={() => console.log('hover', state.count)}>{state.count}</span>
+        </div>
+    );
+});
+
+export const Footer = component$(() => {
+    return (
+        <footer onClick$={() => console.log('footer')}>Footer</footer>
+    );
+});
+"#
+			.into(),
+			path: "test.tsx".into(),
+			map: None,
+		}],
+		mode: EmitMode::Prod,
+		symbol_traces: Some(traces),
+		..TransformModulesOptions::default()
+	})
+	.unwrap();
+
+	let entries: HashMap<String, Option<String>> = output
+		.modules
+		.iter()
+		.filter_map(|module| module.hook.as_ref())
+		.map(|hook| {
+			(
+				hook.hash.to_string(),
+				hook.entry.as_ref().map(|e| e.to_string()),
+			)
+		})
+		.collect();
+	let entry = |name: &str| Some(name.to_string());
+	assert_eq!(entries["zTmRHlL09Gg"], entry("entry_mSjGM0NCKTA"));
+	assert_eq!(entries["mSjGM0NCKTA"], entry("entry_mSjGM0NCKTA"));
+	assert_eq!(entries["CBpnXgTY6FY"], entry("entry_CBpnXgTY6FY"));
+	assert_eq!(entries["gzsAFePmakY"], entry("entry_CBpnXgTY6FY"));
+	// Loaded alone, or never loaded
+	assert_eq!(entries["iB2Af6JwrPc"], None);
+	assert_eq!(entries["t81gj5Z0vOM"], None);
+	assert!(output
+		.modules
+		.iter()
+		.any(|module| module.is_entry && module.path == "entry_CBpnXgTY6FY.js"));
+
+	assert_eq!(
+		output.trace_report,
+		Some(TraceReport {
+			sessions: 2,
+			symbol_loads: 9,
+			requests: 5,
+		})
+	);
 }
 
 #[test]
-fn example_noop_dev_mode() {
-	test_input!(TestInput {
-		code: r#"
-import { component$, useStore, serverStuff$, $ } from '@builder.io/qwik';
+fn transform_with_custom_entry_policy() {
+	struct ByKind;
+
+	impl EntryPolicy for ByKind {
+		fn get_entry_for_sym(
+			&self,
+			_hash: &str,
+			_context: &[String],
+			hook_data: &HookData,
+		) -> Option<JsWord> {
+			match hook_data.ctx_kind {
+				HookKind::EventHandler => Some("entry_events".into()),
+				_ => None,
+			}
+		}
+	}
 
-export const App = component$(() => {
-    const stuff = useStore();
-    serverStuff$(async () => {
-        // should be removed but keep scope
-        console.log(stuff.count)
-    })
-    serverStuff$(async () => {
-        // should be removed
-    })
+	let output = transform_modules_with_entry_policy(
+		TransformModulesOptions {
+			input: vec![TransformModuleInput {
+				code: r#"
+import { component$ } from '@builder.io/qwik';
 
-    return (
-        <Cmp>
-            <p class="stuff" 
-                shouldRemove$={() => stuff.count}
-                onClick$={() => console.log('warn')}
-            >
-                Hello Qwik
-            </p>
-        </Cmp>
-    );
+export const App = component$(() => {
+    return <button onClick$={() => console.log('click')} onDblClick$={() => console.log('double')}></button>;
 });
 "#
-		.to_string(),
-		mode: EmitMode::Dev,
-		transpile_ts: true,
-		transpile_jsx: true,
-		strip_event_handlers: true,
-		strip_ctx_name: Some(vec!["server".into()]),
-		..TestInput::default()
-	});
+				.into(),
+				path: "app.tsx".into(),
+				map: None,
+			}],
+			mode: EmitMode::Prod,
+			entry_strategy: EntryStrategy::Single,
+			..TransformModulesOptions::default()
+		},
+		&ByKind,
+	)
+	.unwrap();
+
+	let mut entries: Vec<_> = output
+		.modules
+		.iter()
+		.filter_map(|module| module.hook.as_ref())
+		.map(|hook| (hook.ctx_name.to_string(), hook.entry.clone()))
+		.collect();
+	entries.sort();
+	assert_eq!(
+		entries,
+		vec![
+			("component$".into(), None),
+			("onClick$".into(), Some("entry_events".into())),
+			("onDblClick$".into(), Some("entry_events".into())),
+		]
+	);
+	assert!(output
+		.modules
+		.iter()
+		.any(|module| module.is_entry && module.path == "entry_events.js"));
 }
 
 // TODO(misko): Make this test work by implementing strict serialization.
@@ -3567,6 +5235,12 @@ struct TestInput {
 	pub src_dir: String,
 	pub root_dir: Option<String>,
 	pub manual_chunks: Option<HashMap<String, JsWord>>,
+	pub sized_chunks: Option<SizedChunks>,
+	pub symbol_traces: Option<SymbolTraces>,
+	pub entry_rules: Option<Vec<EntryRule>>,
+	pub es_target: Option<EsTarget>,
+	pub jsx_import_source: Option<String>,
+	pub define: Option<HashMap<String, serde_json::Value>>,
 	pub entry_strategy: EntryStrategy,
 	pub minify: MinifyMode,
 	pub transpile_ts: bool,
@@ -3592,6 +5266,12 @@ impl TestInput {
 			root_dir: None,
 			code: "/user/qwik/src/".to_string(),
 			manual_chunks: None,
+			sized_chunks: None,
+			symbol_traces: None,
+			entry_rules: None,
+			es_target: None,
+			jsx_import_source: None,
+			define: None,
 			entry_strategy: EntryStrategy::Hook,
 			minify: MinifyMode::Simplify,
 			transpile_ts: false,
@@ -3610,3 +5290,77 @@ impl TestInput {
 		}
 	}
 }
+
+#[cfg(feature = "fs")]
+impl Default for TransformFsOptions {
+	fn default() -> Self {
+		Self {
+			src_dir: "/user/qwik/src/".to_string(),
+			root_dir: None,
+			vendor_roots: vec![],
+			glob: None,
+			minify: MinifyMode::Simplify,
+			entry_strategy: EntryStrategy::Hook,
+			manual_chunks: None,
+			sized_chunks: None,
+			symbol_traces: None,
+			entry_rules: None,
+			es_target: None,
+			jsx_import_source: None,
+			jsx_pragma: None,
+			jsx_pragma_frag: None,
+			define: None,
+			source_maps: false,
+			inline_source_maps: false,
+			sources_content: false,
+			transpile_ts: true,
+			transpile_jsx: true,
+			preserve_filenames: false,
+			explicit_extensions: false,
+			mode: EmitMode::Lib,
+			scope: None,
+			core_module: None,
+			strip_exports: None,
+			strip_ctx_name: None,
+			strip_event_handlers: false,
+			reg_ctx_name: None,
+			is_server: None,
+		}
+	}
+}
+
+impl Default for TransformModulesOptions {
+	fn default() -> Self {
+		Self {
+			src_dir: "/user/qwik/src/".to_string(),
+			root_dir: None,
+			input: vec![],
+			source_maps: false,
+			inline_source_maps: false,
+			sources_content: false,
+			minify: MinifyMode::Simplify,
+			transpile_ts: true,
+			transpile_jsx: true,
+			preserve_filenames: false,
+			entry_strategy: EntryStrategy::Hook,
+			manual_chunks: None,
+			sized_chunks: None,
+			symbol_traces: None,
+			entry_rules: None,
+			es_target: None,
+			jsx_import_source: None,
+			jsx_pragma: None,
+			jsx_pragma_frag: None,
+			define: None,
+			explicit_extensions: false,
+			mode: EmitMode::Lib,
+			scope: None,
+			core_module: None,
+			strip_exports: None,
+			strip_ctx_name: None,
+			strip_event_handlers: false,
+			reg_ctx_name: None,
+			is_server: None,
+		}
+	}
+}
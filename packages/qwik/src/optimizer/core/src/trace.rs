@@ -0,0 +1,106 @@
+use crate::parse::TransformOutput;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use swc_atoms::JsWord;
+
+/// Symbols loaded within this many milliseconds of each other are considered loaded together.
+const DEFAULT_TRACE_WINDOW: f64 = 100.0;
+
+/// QRL symbol loads recorded in real sessions, used to group symbols into entries.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolTraces {
+	/// The symbols loaded by each session, in the order they were loaded.
+	pub sessions: Vec<Vec<SymbolLoad>>,
+	/// Milliseconds between two loads for them to be part of the same burst, 100 by default.
+	pub window: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolLoad {
+	/// Hash of the loaded symbol.
+	pub hash: String,
+	/// When the symbol was loaded, in milliseconds.
+	pub time: f64,
+}
+
+/// How the recorded sessions would load the output.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceReport {
+	pub sessions: usize,
+	/// Symbols of the output loaded by the sessions, the requests without any entry.
+	pub symbol_loads: usize,
+	/// Bundles requested by the sessions, each one counted once per session.
+	pub requests: usize,
+}
+
+impl SymbolTraces {
+	/// Splits every session into bursts of loads, and groups the symbols that are always loaded
+	/// in the same bursts. Returns the entry of the grouped symbols keyed by hash, like
+	/// `manual_chunks`, symbols that are loaded on their own are left out.
+	pub fn entries(&self) -> HashMap<String, JsWord> {
+		let window = self.window.unwrap_or(DEFAULT_TRACE_WINDOW);
+		let mut bursts_by_symbol: HashMap<&str, Vec<(usize, usize)>> = HashMap::new();
+		for (session_index, session) in self.sessions.iter().enumerate() {
+			let mut loads: Vec<&SymbolLoad> = session.iter().collect();
+			loads.sort_by(|a, b| a.time.total_cmp(&b.time));
+			let mut burst = 0;
+			let mut last_time = None;
+			for load in loads {
+				if last_time.map_or(false, |last| load.time - last > window) {
+					burst += 1;
+				}
+				last_time = Some(load.time);
+				let bursts = bursts_by_symbol.entry(&load.hash).or_default();
+				if bursts.last() != Some(&(session_index, burst)) {
+					bursts.push((session_index, burst));
+				}
+			}
+		}
+
+		let mut groups: BTreeMap<Vec<(usize, usize)>, Vec<&str>> = BTreeMap::new();
+		for (hash, bursts) in bursts_by_symbol {
+			groups.entry(bursts).or_default().push(hash);
+		}
+		let mut entries = HashMap::new();
+		for mut hashes in groups.into_values().filter(|hashes| hashes.len() > 1) {
+			hashes.sort_unstable();
+			let entry = JsWord::from(["entry_", hashes[0]].concat());
+			for hash in hashes {
+				entries.insert(hash.to_string(), entry.clone());
+			}
+		}
+		entries
+	}
+
+	/// Counts the bundles of `output` that the recorded sessions would request.
+	pub fn report(&self, output: &TransformOutput) -> TraceReport {
+		let manifest = output.get_manifest();
+		let bundle_by_hash: HashMap<&str, &JsWord> = manifest
+			.symbols
+			.iter()
+			.filter_map(|(name, hook)| Some((hook.hash.as_ref(), manifest.mapping.get(name)?)))
+			.collect();
+		let mut report = TraceReport {
+			sessions: self.sessions.len(),
+			..TraceReport::default()
+		};
+		for session in &self.sessions {
+			let mut loaded = HashSet::new();
+			let mut requested = HashSet::new();
+			for load in session {
+				if let Some(bundle) = bundle_by_hash.get(load.hash.as_str()) {
+					if loaded.insert(&load.hash) {
+						report.symbol_loads += 1;
+					}
+					if requested.insert(bundle) {
+						report.requests += 1;
+					}
+				}
+			}
+		}
+		report
+	}
+}
@@ -4,15 +4,16 @@ use crate::collector::{
 };
 use crate::entry_strategy::EntryPolicy;
 use crate::has_branches::{is_conditional_jsx, is_conditional_jsx_block};
+use crate::hash::StableHasher;
 use crate::inlined_fn::{convert_inlined_fn, render_expr};
 use crate::is_immutable::is_immutable_expr;
-use crate::parse::{EmitMode, PathData};
+use crate::lint::{fn_decl_to_qrl, qrl_implementation_stub, CaptureLint};
+use crate::parse::{parse_filename, EmitMode, PathData};
 use crate::words::*;
 use crate::{errors, EntryStrategy};
 use base64::Engine;
 use path_slash::PathExt;
 use serde::{Deserialize, Serialize};
-use std::collections::hash_map::DefaultHasher;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Write as _;
 use std::hash::Hash;
@@ -23,7 +24,11 @@ use std::str;
 use swc_atoms::{js_word, JsWord};
 use swc_common::comments::{Comments, SingleThreadedComments};
 use swc_common::SyntaxContext;
-use swc_common::{errors::HANDLER, sync::Lrc, SourceMap, Span, Spanned, DUMMY_SP};
+use swc_common::{
+	errors::{Applicability, HANDLER},
+	sync::Lrc,
+	SourceMap, Span, Spanned, DUMMY_SP,
+};
 use swc_ecmascript::ast::{self};
 use swc_ecmascript::utils::{private_ident, quote_ident, ExprFactory};
 use swc_ecmascript::visit::{noop_fold_type, Fold, FoldWith, VisitWith};
@@ -97,6 +102,9 @@ pub struct QwikTransform<'a> {
 	extra_bottom_items: BTreeMap<Id, ast::ModuleItem>,
 	stack_ctxt: Vec<String>,
 	decl_stack: Vec<Vec<IdPlusType>>,
+	capture_lints: HashMap<Id, CaptureLint>,
+	fn_decl_fixes: HashMap<Id, Vec<(Span, String)>>,
+	first_stmt_span: Option<Span>,
 	in_component: bool,
 	marker_functions: HashMap<Id, JsWord>,
 	jsx_functions: HashSet<Id>,
@@ -121,6 +129,9 @@ pub struct QwikTransformOptions<'a> {
 	pub entry_policy: &'a dyn EntryPolicy,
 	pub extension: JsWord,
 	pub core_module: JsWord,
+	pub jsx_import_source: Option<JsWord>,
+	pub jsx_pragma: JsWord,
+	pub jsx_pragma_frag: JsWord,
 	pub explicit_extensions: bool,
 	pub comments: Option<&'a SingleThreadedComments>,
 	pub global_collect: GlobalCollect,
@@ -144,6 +155,28 @@ fn convert_signal_word(id: &JsWord) -> Option<JsWord> {
 		None
 	}
 }
+/// The import binding of the classic JSX factory `name`. The default `h` and `Fragment` must
+/// be imported from the core module or the JSX import source, a custom pragma set with the
+/// options is found by its local name.
+fn jsx_factory_local(
+	options: &QwikTransformOptions,
+	name: &JsWord,
+	default: &JsWord,
+) -> Option<Id> {
+	if name != default {
+		return options.global_collect.get_import_by_local(name);
+	}
+	options
+		.global_collect
+		.get_imported_local(name, &options.core_module)
+		.or_else(|| {
+			options
+				.jsx_import_source
+				.as_ref()
+				.and_then(|source| options.global_collect.get_imported_local(name, source))
+		})
+}
+
 impl<'a> QwikTransform<'a> {
 	pub fn new(options: QwikTransformOptions<'a>) -> Self {
 		let mut marker_functions = HashMap::new();
@@ -159,13 +192,25 @@ impl<'a> QwikTransform<'a> {
 			}
 		}
 
-		let mut hasher = DefaultHasher::new();
+		let mut hasher = StableHasher::new();
 		let local_file_name = options.path_data.rel_path.to_slash_lossy();
 		if let Some(scope) = options.scope {
 			hasher.write(scope.as_bytes());
 		}
 		hasher.write(local_file_name.as_bytes());
 
+		let jsx_import_source = options.jsx_import_source.as_deref();
+		let is_jsx_source = |source: &str| jsx_import_source == Some(source);
+		let is_jsx_runtime = |source: &str| {
+			jsx_import_source.map_or(false, |jsx_import_source| {
+				source
+					.strip_prefix(jsx_import_source)
+					.map_or(false, |runtime| {
+						runtime == JSX_RUNTIME_SUBMODULE || runtime == JSX_DEV_RUNTIME_SUBMODULE
+					})
+			})
+		};
+
 		let jsx_functions = options
 			.global_collect
 			.imports
@@ -176,11 +221,12 @@ impl<'a> QwikTransform<'a> {
 					import.source.as_ref(),
 					import.specifier.as_ref(),
 				) {
-					(ImportKind::Named, "@builder.io/qwik", "jsx") => Some(id.clone()),
-					(ImportKind::Named, "@builder.io/qwik", "jsxs") => Some(id.clone()),
-					(ImportKind::Named, "@builder.io/qwik", "jsxDEV") => Some(id.clone()),
-					(ImportKind::Named, "@builder.io/qwik/jsx-runtime", _) => Some(id.clone()),
-					(ImportKind::Named, "@builder.io/qwik/jsx-dev-runtime", _) => Some(id.clone()),
+					(ImportKind::Named, source, "jsx" | "jsxs" | "jsxDEV")
+						if is_jsx_source(source) =>
+					{
+						Some(id.clone())
+					}
+					(ImportKind::Named, source, _) if is_jsx_runtime(source) => Some(id.clone()),
 					_ => None,
 				}
 			})
@@ -196,16 +242,14 @@ impl<'a> QwikTransform<'a> {
 					import.source.as_ref(),
 					import.specifier.as_ref(),
 				) {
-					(
-						ImportKind::Named,
-						"@builder.io/qwik/jsx-runtime" | "@builder.io/qwik/jsx-dev-runtime",
-						"Fragment",
-					) => Some(id.clone()),
-					(
-						ImportKind::Named,
-						"@builder.io/qwik",
-						"Fragment" | "RenderOnce" | "HTMLFragment",
-					) => Some(id.clone()),
+					(ImportKind::Named, source, "Fragment") if is_jsx_runtime(source) => {
+						Some(id.clone())
+					}
+					(ImportKind::Named, source, "Fragment" | "RenderOnce" | "HTMLFragment")
+						if source == &*options.core_module =>
+					{
+						Some(id.clone())
+					}
 					(ImportKind::Named, "@builder.io/qwik-city", "Link") => Some(id.clone()),
 					(_, source, _) => {
 						if source.ends_with("?jsx") || source.ends_with(".md") {
@@ -222,6 +266,9 @@ impl<'a> QwikTransform<'a> {
 			jsx_key_counter: 0,
 			stack_ctxt: Vec::with_capacity(16),
 			decl_stack: Vec::with_capacity(32),
+			capture_lints: HashMap::new(),
+			fn_decl_fixes: HashMap::new(),
+			first_stmt_span: None,
 			in_component: false,
 			hooks: Vec::with_capacity(16),
 			hook_stack: Vec::with_capacity(16),
@@ -241,12 +288,8 @@ impl<'a> QwikTransform<'a> {
 			inlined_qrl_fn: options
 				.global_collect
 				.get_imported_local(&_INLINED_QRL, &options.core_module),
-			h_fn: options
-				.global_collect
-				.get_imported_local(&H, &options.core_module),
-			fragment_fn: options
-				.global_collect
-				.get_imported_local(&FRAGMENT, &options.core_module),
+			h_fn: jsx_factory_local(&options, &options.jsx_pragma, &H),
+			fragment_fn: jsx_factory_local(&options, &options.jsx_pragma_frag, &FRAGMENT),
 			marker_functions,
 			jsx_functions,
 			immutable_function_cmp,
@@ -330,7 +373,7 @@ impl<'a> QwikTransform<'a> {
 		} else {
 			write!(display_name, "_{}", index).unwrap();
 		}
-		let mut hasher = DefaultHasher::new();
+		let mut hasher = StableHasher::new();
 		let local_file_name = self.options.path_data.rel_path.to_slash_lossy();
 		if let Some(scope) = self.options.scope {
 			hasher.write(scope.as_bytes());
@@ -647,6 +690,19 @@ impl<'a> QwikTransform<'a> {
 			});
 			scoped_idents = vec![];
 		}
+		for id in &scoped_idents {
+			if let Some(lint) = self.capture_lints.get(id) {
+				HANDLER.with(|handler| {
+					handler
+						.struct_span_warn_with_code(
+							first_arg_span,
+							&lint.message(&id.0),
+							errors::get_diagnostic_id(lint.error()),
+						)
+						.emit();
+				});
+			}
+		}
 		let hook_data = HookData {
 			extension: self.options.extension.clone(),
 			local_idents,
@@ -669,15 +725,23 @@ impl<'a> QwikTransform<'a> {
 					}
 					if invalid_decl.iter().any(|entry| entry.0 == *id) {
 						HANDLER.with(|handler| {
-							handler
-								.struct_err_with_code(
-									&format!(
-										"Reference to identifier '{}' can not be used inside a Qrl($) scope because it's a function",
-										id.0
-									),
-									errors::get_diagnostic_id(errors::Error::FunctionReference),
-								)
-								.emit();
+							let message = format!(
+								"Reference to identifier '{}' can not be used inside a Qrl($) scope because it's a function",
+								id.0
+							);
+							let code = errors::get_diagnostic_id(errors::Error::FunctionReference);
+							if let Some(fix) = self.fn_decl_fixes.get(id) {
+								handler
+									.struct_span_err_with_code(fix[0].0, &message, code)
+									.multipart_suggestion_with_applicability(
+										&format!("Wrap '{}' with $() to turn it into a Qrl", id.0),
+										fix.clone(),
+										Applicability::MaybeIncorrect,
+									)
+									.emit();
+							} else {
+								handler.struct_err_with_code(&message, code).emit();
+							}
 						});
 					}
 				}
@@ -1695,6 +1759,16 @@ impl<'a> Fold for QwikTransform<'a> {
 	noop_fold_type!();
 
 	fn fold_module(&mut self, node: ast::Module) -> ast::Module {
+		self.first_stmt_span = node
+			.body
+			.iter()
+			.find(|item| {
+				!matches!(
+					item,
+					ast::ModuleItem::ModuleDecl(ast::ModuleDecl::Import(_))
+				)
+			})
+			.map(|item| item.span().shrink_to_lo());
 		let mut body = Vec::with_capacity(node.body.len() + 10);
 		let mut module_body = node
 			.body
@@ -1763,6 +1837,11 @@ impl<'a> Fold for QwikTransform<'a> {
 					IdentType::Var(false)
 				};
 				current_scope.extend(identifiers.into_iter().map(|(id, _)| (id, ident_type)));
+				if let ast::Pat::Ident(ident) = &decl.name {
+					if let Some(lint) = CaptureLint::classify(node.kind, decl.init.as_deref()) {
+						self.capture_lints.insert(id!(ident.id), lint);
+					}
+				}
 			}
 		}
 		node.fold_children_with(self)
@@ -1784,6 +1863,21 @@ impl<'a> Fold for QwikTransform<'a> {
 	fn fold_fn_decl(&mut self, node: ast::FnDecl) -> ast::FnDecl {
 		if let Some(current_scope) = self.decl_stack.last_mut() {
 			current_scope.push((id!(node.ident), IdentType::Fn));
+			let dollar = self
+				.qhook_fn
+				.as_ref()
+				.map_or(QHOOK.clone(), |id| id.0.clone());
+			if let Some(mut fix) = fn_decl_to_qrl(&node, &dollar) {
+				if self.qhook_fn.is_none() {
+					if let Some(span) = self.first_stmt_span {
+						fix.push((
+							span.shrink_to_lo(),
+							format!("import {{ $ }} from '{}';\n", self.options.core_module),
+						));
+					}
+				}
+				self.fn_decl_fixes.insert(id!(node.ident), fix);
+			}
 		}
 		self.stack_ctxt.push(node.ident.sym.to_string());
 
@@ -2131,13 +2225,22 @@ impl<'a> Fold for QwikTransform<'a> {
 							.map_or_else(
 								|| {
 									HANDLER.with(|handler| {
-										handler
+										let mut diagnostic = handler
 											.struct_span_err_with_code(
 												ident.span,
 												&format!("Found '{}' but did not find the corresponding '{}' exported in the same file. Please check that it is exported and spelled correctly", &ident.sym, &new_specifier),
 												errors::get_diagnostic_id(errors::Error::MissingQrlImplementation),
-										)
-											.emit();
+										);
+										if let Some(span) = self.first_stmt_span {
+											let (is_type_script, _) = parse_filename(self.options.path_data);
+											diagnostic.span_suggestion_with_applicability(
+												span,
+												&format!("Add the missing '{}' export", &new_specifier),
+												qrl_implementation_stub(&new_specifier, is_type_script),
+												Applicability::MaybeIncorrect,
+											);
+										}
+										diagnostic.emit();
 									});
 								},
 								|new_local| {
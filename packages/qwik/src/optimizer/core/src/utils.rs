@@ -21,9 +21,12 @@ impl SourceLocation {
 		// - SWC has 0-based columns, ours are 1-based (column + 1)
 		// = +-0
 
+		// SWC's positions are offsets in the `SourceMap`, ours are offsets in the file
+		let file_start = start.file.start_pos.0;
+
 		Self {
-			lo: span.lo.0 as usize,
-			hi: span.hi.0 as usize,
+			lo: span.lo.0.saturating_sub(file_start) as usize,
+			hi: span.hi.0.saturating_sub(file_start) as usize,
 			start_line: start.line,
 			start_col: start.col_display + 1,
 			end_line: end.line,
@@ -41,7 +44,7 @@ impl PartialOrd for SourceLocation {
 	}
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Diagnostic {
 	pub category: DiagnosticCategory,
@@ -50,10 +53,54 @@ pub struct Diagnostic {
 	pub message: String,
 	pub highlights: Option<Vec<SourceLocation>>,
 	pub suggestions: Option<Vec<String>>,
+	pub fixes: Option<Vec<DiagnosticFix>>,
 	pub scope: DiagnosticScope,
 }
 
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+/// A change to the source code that resolves a diagnostic, only the `MachineApplicable` ones
+/// can be applied without review.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticFix {
+	pub message: String,
+	pub edits: Vec<DiagnosticEdit>,
+	pub applicability: DiagnosticApplicability,
+}
+
+/// How confident the optimizer is that a fix is what the user intended.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum DiagnosticApplicability {
+	/// The fix is definitely what the user intended, it can be applied automatically.
+	MachineApplicable,
+	/// The fix may be what the user intended, but it is uncertain.
+	MaybeIncorrect,
+	/// The fix contains placeholders like `(...)` that the user has to fill in.
+	HasPlaceholders,
+	Unspecified,
+}
+
+impl From<swc_common::errors::Applicability> for DiagnosticApplicability {
+	fn from(applicability: swc_common::errors::Applicability) -> Self {
+		use swc_common::errors::Applicability;
+		match applicability {
+			Applicability::MachineApplicable => Self::MachineApplicable,
+			Applicability::MaybeIncorrect => Self::MaybeIncorrect,
+			Applicability::HasPlaceholders => Self::HasPlaceholders,
+			Applicability::Unspecified => Self::Unspecified,
+		}
+	}
+}
+
+/// Replaces the source code in `loc` with `replacement`, an empty `loc` is an insertion.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticEdit {
+	pub loc: SourceLocation,
+	pub replacement: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum DiagnosticCategory {
 	/// Fails the build with an error.
@@ -64,7 +111,7 @@ pub enum DiagnosticCategory {
 	SourceError,
 }
 
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum DiagnosticScope {
 	Optimizer,
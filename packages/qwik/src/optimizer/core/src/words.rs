@@ -3,6 +3,10 @@ use swc_atoms::JsWord;
 
 pub const SIGNAL: char = '$';
 pub const LONG_SUFFIX: &str = "Qrl";
+// Submodules of the core module, `@builder.io/qwik/build` for the default one
+pub const BUILD_SUBMODULE: &str = "/build";
+pub const JSX_RUNTIME_SUBMODULE: &str = "/jsx-runtime";
+pub const JSX_DEV_RUNTIME_SUBMODULE: &str = "/jsx-dev-runtime";
 
 lazy_static! {
 	pub static ref REF: JsWord = JsWord::from("ref");
@@ -21,11 +25,9 @@ lazy_static! {
 	pub static ref Q_SYNC: JsWord = JsWord::from("sync$");
 	pub static ref QWIK_INTERNAL: JsWord = JsWord::from("qwik");
 	pub static ref BUILDER_IO_QWIK: JsWord = JsWord::from("@builder.io/qwik");
-	pub static ref BUILDER_IO_QWIK_BUILD: JsWord = JsWord::from("@builder.io/qwik/build");
-	pub static ref BUILDER_IO_QWIK_JSX: JsWord = JsWord::from("@builder.io/qwik/jsx-runtime");
-	pub static ref BUILDER_IO_QWIK_JSX_DEV: JsWord =
-		JsWord::from("@builder.io/qwik/jsx-dev-runtime");
 	pub static ref QCOMPONENT: JsWord = JsWord::from("component$");
+	pub static ref USE_TASK: JsWord = JsWord::from("useTask$");
+	pub static ref USE_VISIBLE_TASK: JsWord = JsWord::from("useVisibleTask$");
 	pub static ref USE_LEXICAL_SCOPE: JsWord = JsWord::from("useLexicalScope");
 	pub static ref H: JsWord = JsWord::from("h");
 	pub static ref FRAGMENT: JsWord = JsWord::from("Fragment");
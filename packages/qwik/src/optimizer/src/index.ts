@@ -2,16 +2,26 @@ export { createOptimizer } from './optimizer';
 export { versions } from './versions';
 
 export type {
+  AnalyzedHook,
+  AnalyzeModulesOptions,
+  AnalyzeOutput,
+  CapturedIdent,
   ComponentEntryStrategy,
   Diagnostic,
+  DiagnosticApplicability,
   DiagnosticCategory,
+  DiagnosticEdit,
+  DiagnosticFix,
+  EntryRule,
   EntryStrategy,
+  EsTarget,
   GlobalInjections,
   HookAnalysis,
   HookEntryStrategy,
   InlineEntryStrategy,
   InsightManifest,
   MinifyMode,
+  ModuleAnalysis,
   Optimizer,
   OptimizerOptions,
   OptimizerSystem,
@@ -21,12 +31,16 @@ export type {
   QwikSymbol,
   ResolvedManifest,
   SingleEntryStrategy,
+  SizedEntryStrategy,
   SmartEntryStrategy,
   SourceLocation,
   SourceMapsOption,
+  SymbolLoad,
   SymbolMapper,
   SymbolMapperFn,
+  SymbolTraces,
   SystemEnvironment,
+  TraceReport,
   TransformFsOptions,
   TransformModule,
   TransformModuleInput,
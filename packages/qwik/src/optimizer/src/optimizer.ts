@@ -5,8 +5,10 @@ import {
   type PlatformBinding,
 } from './platform';
 import type {
+  AnalyzeModulesOptions,
   TransformModulesOptions,
   TransformFsOptions,
+  TransformOutput,
   Optimizer,
   OptimizerSystem,
   OptimizerOptions,
@@ -24,12 +26,18 @@ export const createOptimizer = async (optimizerOptions: OptimizerOptions = {}) =
     transformModulesSync(opts: TransformModulesOptions) {
       return transformModulesSync(binding, opts);
     },
-    async transformFs(opts: TransformFsOptions) {
-      return transformFsAsync(sys, binding, opts);
+    async transformFs(opts: TransformFsOptions, onOutput?: (output: TransformOutput) => void) {
+      return transformFsAsync(sys, binding, opts, onOutput);
     },
     transformFsSync(opts: TransformFsOptions) {
       return transformFsSync(binding, opts);
     },
+    async analyzeModules(opts: AnalyzeModulesOptions) {
+      return analyzeModulesSync(binding, opts);
+    },
+    analyzeModulesSync(opts: AnalyzeModulesOptions) {
+      return analyzeModulesSync(binding, opts);
+    },
     sys,
   };
 
@@ -48,21 +56,40 @@ const transformFsSync = (binding: PlatformBinding, opts: TransformFsOptions) =>
   throw new Error('Not implemented');
 };
 
+/** Analyzes the input code string without generating code, does not access the file system. */
+const analyzeModulesSync = (binding: PlatformBinding, opts: AnalyzeModulesOptions) => {
+  if (binding.analyze_modules) {
+    return binding.analyze_modules(convertOptions(opts));
+  }
+  throw new Error('Not implemented');
+};
+
 const transformFsAsync = async (
   sys: OptimizerSystem,
   binding: PlatformBinding,
-  fsOpts: TransformFsOptions
+  fsOpts: TransformFsOptions,
+  onOutput?: (output: TransformOutput) => void
 ) => {
   if (binding.transform_fs && !sys.getInputFiles) {
-    return binding.transform_fs(convertOptions(fsOpts));
+    if (onOutput && binding.transform_fs_streaming) {
+      // The outputs are not kept in memory, only their metadata is returned
+      return binding.transform_fs_streaming(convertOptions(fsOpts), onOutput);
+    }
+    return withOutput(binding.transform_fs(convertOptions(fsOpts)), onOutput);
   }
 
   const getInputFiles = await getPlatformInputFiles(sys);
 
   if (getInputFiles) {
-    const input = await getInputFiles(fsOpts.srcDir);
+    // Like the native binding, the globs are matched relative to the root of each file
+    const matchesGlob = createGlobFilter(fsOpts.glob);
+    const getRootFiles = async (root: string) =>
+      (await getInputFiles(root)).filter((file) =>
+        matchesGlob(sys.path.relative(root, file.path).replace(/\\/g, '/'))
+      );
+    const input = await getRootFiles(fsOpts.srcDir);
     for (const root of fsOpts.vendorRoots) {
-      const rootFiles = await getInputFiles(root);
+      const rootFiles = await getRootFiles(root);
       input.push(...rootFiles);
     }
     input.forEach((file) => {
@@ -74,6 +101,8 @@ const transformFsAsync = async (
       entryStrategy: fsOpts.entryStrategy!,
       minify: fsOpts.minify!,
       sourceMaps: !!fsOpts.sourceMaps,
+      inlineSourceMaps: !!fsOpts.inlineSourceMaps,
+      sourcesContent: !!fsOpts.sourcesContent,
       transpileTs: fsOpts.transpileTs!,
       transpileJsx: fsOpts.transpileJsx!,
       explicitExtensions: fsOpts.explicitExtensions!,
@@ -86,23 +115,107 @@ const transformFsAsync = async (
       stripCtxName: fsOpts.stripCtxName!,
       stripExports: fsOpts.stripExports!,
       isServer: fsOpts.isServer!,
+      symbolTraces: fsOpts.symbolTraces!,
+      entryRules: fsOpts.entryRules!,
+      esTarget: fsOpts.esTarget!,
+      coreModule: fsOpts.coreModule!,
+      jsxImportSource: fsOpts.jsxImportSource!,
+      jsxPragma: fsOpts.jsxPragma!,
+      jsxPragmaFrag: fsOpts.jsxPragmaFrag!,
+      define: fsOpts.define!,
     };
-    return binding.transform_modules(convertOptions(modulesOpts));
+    return withOutput(binding.transform_modules(convertOptions(modulesOpts)), onOutput);
   }
 
   throw new Error('Not implemented');
 };
 
+/**
+ * Matches relative paths with the include and exclude (prefixed with `!`) glob patterns. Every
+ * path is included when there are no include patterns.
+ */
+const createGlobFilter = (glob: string | string[] | undefined) => {
+  const include: RegExp[] = [];
+  const exclude: RegExp[] = [];
+  for (const pattern of typeof glob === 'string' ? [glob] : glob ?? []) {
+    if (pattern.startsWith('!')) {
+      exclude.push(globToRegExp(pattern.slice(1)));
+    } else {
+      include.push(globToRegExp(pattern));
+    }
+  }
+  return (path: string) =>
+    (include.length === 0 || include.some((re) => re.test(path))) &&
+    !exclude.some((re) => re.test(path));
+};
+
+/** `*` and `?` do not match `/`, a `**` path segment matches any number of directories. */
+const globToRegExp = (glob: string) => {
+  const segments = glob.replace(/^\.\//, '').split('/');
+  let source = '';
+  segments.forEach((segment, index) => {
+    const last = index === segments.length - 1;
+    if (segment === '**') {
+      source += last ? '.*' : '(?:[^/]*/)*';
+      return;
+    }
+    let i = 0;
+    while (i < segment.length) {
+      const char = segment[i++];
+      if (char === '*') {
+        source += '[^/]*';
+      } else if (char === '?') {
+        source += '[^/]';
+      } else if (char === '[' && segment.indexOf(']', i + 1) !== -1) {
+        const end = segment.indexOf(']', i + 1);
+        const negated = segment[i] === '!';
+        const chars = segment.slice(negated ? i + 1 : i, end).replace(/[\\^\]]/g, '\\$&');
+        source += `[${negated ? '^' : ''}${chars}]`;
+        i = end + 1;
+      } else {
+        source += char.replace(/[.*+?^${}()|[\]\\]/g, '\\$&');
+      }
+    }
+    if (!last) {
+      source += '/';
+    }
+  });
+  return new RegExp(`^${source}$`);
+};
+
+/** Passes the whole output to `onOutput` when the binding can not stream it. */
+const withOutput = (output: TransformOutput, onOutput?: (output: TransformOutput) => void) => {
+  if (!onOutput) {
+    return output;
+  }
+  onOutput(output);
+  return {
+    ...output,
+    modules: output.modules.map((module) => ({ ...module, code: '', map: null })),
+  };
+};
+
 const convertOptions = (opts: any) => {
   const output: any = {
     minify: 'simplify',
     sourceMaps: false,
+    inlineSourceMaps: false,
+    sourcesContent: false,
     transpileTs: false,
     transpileJsx: false,
     preserveFilenames: false,
     explicitExtensions: false,
     mode: 'lib',
     manualChunks: undefined,
+    sizedChunks: undefined,
+    symbolTraces: undefined,
+    entryRules: undefined,
+    esTarget: undefined,
+    coreModule: undefined,
+    jsxImportSource: undefined,
+    jsxPragma: undefined,
+    jsxPragmaFrag: undefined,
+    define: undefined,
     scope: undefined,
     regCtxName: undefined,
     stripEventHandlers: false,
@@ -118,5 +231,9 @@ const convertOptions = (opts: any) => {
   });
   output.entryStrategy = opts.entryStrategy?.type ?? 'smart';
   output.manualChunks = opts.entryStrategy?.manual ?? undefined;
+  if (opts.entryStrategy?.type === 'sized') {
+    const { minSize, maxSize, usage } = opts.entryStrategy;
+    output.sizedChunks = { minSize, maxSize, usage };
+  }
   return output;
 };
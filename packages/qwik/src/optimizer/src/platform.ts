@@ -1,4 +1,5 @@
 import type {
+  AnalyzeOutput,
   OptimizerSystem,
   SystemEnvironment,
   TransformModuleInput,
@@ -249,6 +250,22 @@ export async function loadPlatformBinding(sys: OptimizerSystem) {
         .then((wasm) => mod.default(wasm))
         .then(() => mod);
     } else {
+      // rayon blocks with Atomics.wait, which the main thread does not allow
+      const isWorker = typeof (globalThis as any).WorkerGlobalScope !== 'undefined';
+      if (isWorker && (globalThis as any).crossOriginIsolated) {
+        // ESM WASM Web Worker - Parallel, the thread pool needs shared memory
+        try {
+          const module = await sys.dynamicImport(`./bindings/threads/qwik_wasm.js`);
+          await module.default();
+          await module.initThreadPool(navigator.hardwareConcurrency);
+          return module;
+        } catch (e) {
+          console.warn(
+            `Unable to load the parallel wasm binding. Falling back to the sequential build.`,
+            (e as Error)?.message
+          );
+        }
+      }
       const module = await sys.dynamicImport(`./bindings/qwik.wasm.mjs`);
       await module.default();
       return module;
@@ -260,7 +277,13 @@ export async function loadPlatformBinding(sys: OptimizerSystem) {
 
 export interface PlatformBinding {
   transform_fs?: (opts: any) => TransformOutput;
+  /** Returns the output without code, the code of each file is passed to `onOutput` instead. */
+  transform_fs_streaming?: (
+    opts: any,
+    onOutput: (output: TransformOutput) => void
+  ) => TransformOutput;
   transform_modules: (opts: any) => TransformOutput;
+  analyze_modules?: (opts: any) => AnalyzeOutput;
 }
 
 const getEnv = (): SystemEnvironment => {
@@ -6,12 +6,24 @@ export interface Optimizer {
   /** Transforms the input code string, does not access the file system. */
   transformModulesSync(opts: TransformModulesOptions): TransformOutput;
 
-  /** Transforms the directory from the file system. */
-  transformFs(opts: TransformFsOptions): Promise<TransformOutput>;
+  /**
+   * Transforms the directory from the file system. When `onOutput` is given, the output of each
+   * file is passed to it as soon as it is transformed, and the returned modules have no code.
+   */
+  transformFs(
+    opts: TransformFsOptions,
+    onOutput?: (output: TransformOutput) => void
+  ): Promise<TransformOutput>;
 
   /** Transforms the directory from the file system. */
   transformFsSync(opts: TransformFsOptions): TransformOutput;
 
+  /** Finds the hooks, their captures and the diagnostics of the input code, without generating code. */
+  analyzeModules(opts: AnalyzeModulesOptions): Promise<AnalyzeOutput>;
+
+  /** Finds the hooks, their captures and the diagnostics of the input code, without generating code. */
+  analyzeModulesSync(opts: AnalyzeModulesOptions): AnalyzeOutput;
+
   /** Optimizer system use. This can be updated with a custom file system. */
   sys: OptimizerSystem;
 }
@@ -55,6 +67,10 @@ export interface TransformOptions {
   entryStrategy?: EntryStrategy;
   minify?: MinifyMode;
   sourceMaps?: boolean;
+  /** Append the source maps to the code as `sourceMappingURL` data comments. */
+  inlineSourceMaps?: boolean;
+  /** Include the original source code in the `sourcesContent` of the source maps. */
+  sourcesContent?: boolean;
   transpileTs?: boolean;
   transpileJsx?: boolean;
   preserveFilenames?: boolean;
@@ -66,6 +82,63 @@ export interface TransformOptions {
   stripCtxName?: string[];
   stripEventHandlers?: boolean;
   isServer?: boolean;
+  /** Recorded symbol loads, symbols loaded together are grouped into the same entry. */
+  symbolTraces?: SymbolTraces;
+  /**
+   * Rules deciding the entry of the hooks they match, checked in order before the entry strategy
+   * and its manual chunks.
+   */
+  entryRules?: EntryRule[];
+  /** The ECMAScript version of the emitted code, newer syntax is rewritten. */
+  esTarget?: EsTarget;
+  /**
+   * The Qwik core package, defaults to `@builder.io/qwik`. The optimizer also recognizes its
+   * `/build` and `/jsx-runtime` submodules.
+   */
+  coreModule?: string;
+  /**
+   * The module providing the automatic JSX runtime at `<source>/jsx-runtime`, defaults to
+   * the core module. Files can override it with a `@jsxImportSource` comment.
+   */
+  jsxImportSource?: string;
+  /** The function the classic JSX runtime calls, defaults to `h`. */
+  jsxPragma?: string;
+  /** The fragment component of the classic JSX runtime, defaults to `Fragment`. */
+  jsxPragmaFrag?: string;
+  /**
+   * Identifiers replaced by JSON values before the hooks are extracted, the branches made dead are
+   * removed. The keys are globals like `process.env.NODE_ENV` or imports written
+   * `<source>:<specifier>`.
+   */
+  define?: Record<string, unknown>;
+}
+
+/** @public */
+export interface EntryRule {
+  /** Names of the marker functions creating the hook, like `component$` or `onClick$`. */
+  ctxName?: string[];
+  ctxKind?: 'function' | 'eventHandler' | 'jSXProp';
+  /** Glob matched against the file the hook comes from, relative to the source directory. */
+  origin?: string;
+  /** Whether the hook captures variables from its scope. */
+  captures?: boolean;
+  /** Entry of the matching hooks, when missing each of them gets a file of its own. */
+  entry?: string;
+}
+
+/** @public */
+export interface SymbolTraces {
+  /** The symbols loaded by each session, in the order they were loaded. */
+  sessions: SymbolLoad[][];
+  /** Milliseconds between two loads for them to be part of the same burst, 100 by default. */
+  window?: number;
+}
+
+/** @public */
+export interface SymbolLoad {
+  hash: string;
+  /** When the symbol was loaded, in milliseconds. */
+  time: number;
 }
 
 /** @public */
@@ -73,9 +146,38 @@ export interface TransformModulesOptions extends TransformOptions {
   input: TransformModuleInput[];
 }
 
+/** @public */
+export interface AnalyzeModulesOptions {
+  srcDir: string;
+  rootDir?: string;
+  input: TransformModuleInput[];
+  mode?: EmitMode;
+  scope?: string;
+  isServer?: boolean;
+  /**
+   * The module providing the automatic JSX runtime, like `jsxImportSource` of
+   * `transformModules`.
+   */
+  jsxImportSource?: string;
+  /** The function the classic JSX runtime calls, defaults to `h`. */
+  jsxPragma?: string;
+  /** The fragment component of the classic JSX runtime, defaults to `Fragment`. */
+  jsxPragmaFrag?: string;
+  /**
+   * Identifiers replaced by JSON values before the hooks are extracted, like `define` of
+   * `transformModules`.
+   */
+  define?: Record<string, unknown>;
+}
+
 /** @public */
 export interface TransformFsOptions extends TransformOptions {
   vendorRoots: string[];
+  /**
+   * Include and exclude (prefixed with `!`) glob patterns, relative to the source directory or
+   * vendor root, applied to the discovered files.
+   */
+  glob?: string | string[];
 }
 
 // OPTION INPUTS ***************
@@ -84,6 +186,11 @@ export interface TransformFsOptions extends TransformOptions {
 export interface TransformModuleInput {
   path: string;
   code: string;
+  /**
+   * Source map of the code, when it was generated from another source. The maps of the output
+   * point to the sources of this map.
+   */
+  map?: string;
 }
 
 // RESULT ***************
@@ -94,6 +201,16 @@ export interface TransformOutput {
   diagnostics: Diagnostic[];
   isTypeScript: boolean;
   isJsx: boolean;
+  traceReport: TraceReport | null;
+}
+
+/** @public */
+export interface TraceReport {
+  sessions: number;
+  /** Symbols of the output loaded by the sessions, the requests without any entry. */
+  symbolLoads: number;
+  /** Bundles requested by the sessions, each one counted once per session. */
+  requests: number;
 }
 
 /** @public */
@@ -109,7 +226,31 @@ export interface HookAnalysis {
   ctxKind: 'event' | 'function';
   ctxName: string;
   captures: boolean;
-  loc: [number, number];
+  loc: SourceLocation;
+}
+
+/** @public */
+export interface AnalyzeOutput {
+  modules: ModuleAnalysis[];
+  diagnostics: Diagnostic[];
+}
+
+/** @public */
+export interface ModuleAnalysis {
+  path: string;
+  hooks: AnalyzedHook[];
+}
+
+/** @public */
+export interface AnalyzedHook {
+  hook: HookAnalysis;
+  captured: CapturedIdent[];
+}
+
+/** @public */
+export interface CapturedIdent {
+  name: string;
+  loc: SourceLocation | null;
 }
 
 // RESULT OUTPUT ***************
@@ -122,6 +263,8 @@ export interface TransformModule {
   map: string | null;
   hook: HookAnalysis | null;
   origPath: string | null;
+  imports: string[];
+  dynamicImports: string[];
 }
 
 // DIAGNOSTICS ***************
@@ -135,6 +278,28 @@ export interface Diagnostic {
   message: string;
   highlights: SourceLocation[];
   suggestions: string[] | null;
+  fixes: DiagnosticFix[] | null;
+}
+
+/** @public */
+export interface DiagnosticFix {
+  message: string;
+  edits: DiagnosticEdit[];
+  /** Only `machineApplicable` fixes can be applied without review. */
+  applicability: DiagnosticApplicability;
+}
+
+/** @public */
+export type DiagnosticApplicability =
+  | 'machineApplicable'
+  | 'maybeIncorrect'
+  | 'hasPlaceholders'
+  | 'unspecified';
+
+/** @public */
+export interface DiagnosticEdit {
+  loc: SourceLocation;
+  replacement: string;
 }
 
 /** @public */
@@ -159,14 +324,28 @@ export type EntryStrategy =
   | SingleEntryStrategy
   | HookEntryStrategy
   | ComponentEntryStrategy
-  | SmartEntryStrategy;
+  | SmartEntryStrategy
+  | SizedEntryStrategy;
 
 /** @public */
-export type MinifyMode = 'simplify' | 'none';
+export type MinifyMode = 'minify' | 'simplify' | 'none';
 
 /** @public */
 export type EmitMode = 'dev' | 'prod' | 'lib';
 
+/** @public */
+export type EsTarget =
+  | 'es5'
+  | 'es2015'
+  | 'es2016'
+  | 'es2017'
+  | 'es2018'
+  | 'es2019'
+  | 'es2020'
+  | 'es2021'
+  | 'es2022'
+  | 'esnext';
+
 /** @public */
 export interface InlineEntryStrategy {
   type: 'inline';
@@ -201,6 +380,18 @@ export interface SmartEntryStrategy {
   manual?: Record<string, string>;
 }
 
+/** @public */
+export interface SizedEntryStrategy {
+  type: 'sized';
+  manual?: Record<string, string>;
+  /** Entries smaller than this many bytes are merged together. */
+  minSize?: number;
+  /** Hooks are not added to an entry beyond this many bytes. */
+  maxSize?: number;
+  /** How often each hook is used, keyed by hook hash. */
+  usage?: Record<string, number>;
+}
+
 /**
  * The metadata of the build. One of its uses is storing where QRL symbols are located.
  *
@@ -256,7 +447,7 @@ export interface QwikSymbol {
   ctxName: string;
   captures: boolean;
   parent: string | null;
-  loc: [number, number];
+  loc: SourceLocation;
 }
 
 /** @public */
@@ -2,17 +2,24 @@
 #![deny(clippy::perf)]
 #![deny(clippy::nursery)]
 
+// wee_alloc is not thread safe, the parallel build uses the default allocator
+#[cfg(not(feature = "parallel"))]
 extern crate wee_alloc;
 
+#[cfg(not(feature = "parallel"))]
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
 use js_sys::Error;
-use qwik_core::TransformModulesOptions;
+use qwik_core::{AnalyzeModulesOptions, TransformModulesOptions};
 use serde::ser::Serialize;
 use serde_wasm_bindgen::{from_value, Serializer};
 use wasm_bindgen::prelude::*;
 
+/// Starts the web workers `transform_modules` runs on, it must be awaited before transforming.
+#[cfg(feature = "parallel")]
+pub use wasm_bindgen_rayon::init_thread_pool;
+
 #[wasm_bindgen]
 pub fn transform_modules(config_val: JsValue) -> Result<JsValue, JsValue> {
     let config: TransformModulesOptions = from_value(config_val).map_err(JsValue::from)?;
@@ -24,6 +31,17 @@ pub fn transform_modules(config_val: JsValue) -> Result<JsValue, JsValue> {
     result.serialize(&serializer).map_err(JsValue::from)
 }
 
+#[wasm_bindgen]
+pub fn analyze_modules(config_val: JsValue) -> Result<JsValue, JsValue> {
+    let config: AnalyzeModulesOptions = from_value(config_val).map_err(JsValue::from)?;
+
+    let result = qwik_core::analyze_modules(config)
+        .map_err(|e| Error::from(JsValue::from_str(&e.to_string())))?;
+
+    let serializer = Serializer::new().serialize_maps_as_objects(true);
+    result.serialize(&serializer).map_err(JsValue::from)
+}
+
 // #[wasm_bindgen]
 // pub fn transform_code(config_val: JsValue) -> Result<JsValue, JsValue> {
 //   let input: String = from_value(config_val).map_err(JsValue::from)?;
@@ -1,4 +1,4 @@
-import { type BuildConfig, copyFile, emptyDir, ensureDir } from './util';
+import { type BuildConfig, copyDir, copyFile, emptyDir, ensureDir } from './util';
 import spawn from 'cross-spawn';
 import { join } from 'node:path';
 import { rollup } from 'rollup';
@@ -6,17 +6,22 @@ import { rollup } from 'rollup';
 export async function buildWasmBinding(config: BuildConfig) {
   const srcWasmDir = join(config.srcQwikDir, `wasm`);
   const tmpBuildDir = join(config.tmpDir, `wasm-out`);
+  const tmpThreadsBuildDir = join(config.tmpDir, `wasm-threads-out`);
+  const distThreadsDir = join(config.distBindingsDir, `threads`);
 
   ensureDir(config.distQwikPkgDir);
   ensureDir(config.distBindingsDir);
   emptyDir(tmpBuildDir);
 
-  async function buildForTarget(env = {}) {
+  async function buildForTarget(env = {}, outDir = tmpBuildDir, cargoArgs: string[] = []) {
     const cmd = `wasm-pack`;
-    const args = [`build`, '--target', 'web', `--out-dir`, tmpBuildDir, srcWasmDir];
+    const args = [`build`, '--target', 'web', `--out-dir`, outDir, srcWasmDir];
     if (!config.dev) {
       args.push(`--release`);
     }
+    if (cargoArgs.length > 0) {
+      args.push(`--`, ...cargoArgs);
+    }
 
     await new Promise((resolve, reject) => {
       const child = spawn(cmd, args, {
@@ -37,7 +42,7 @@ export async function buildWasmBinding(config: BuildConfig) {
         }
       });
     });
-    return join(tmpBuildDir, 'qwik_wasm.js');
+    return join(outDir, 'qwik_wasm.js');
   }
 
   const wasmJsBuildPath = await buildForTarget({
@@ -67,5 +72,24 @@ export async function buildWasmBinding(config: BuildConfig) {
     join(config.distBindingsDir, 'qwik_wasm_bg.wasm')
   );
 
+  if (config.wasmThreads) {
+    emptyDir(tmpThreadsBuildDir);
+    // Transforms in parallel on web workers, only usable by cross origin isolated workers. The
+    // workers load the wasm-bindgen-rayon helpers relative to the module, so it is not bundled.
+    // Rebuilding std with atomics needs the rust-src component of the pinned toolchain.
+    await buildForTarget(
+      {
+        CARGO_PROFILE_RELEASE_LTO: true,
+        CARGO_PROFILE_RELEASE_PANIC: 'abort',
+        CARGO_PROFILE_RELEASE_OPT_LEVEL: 'z',
+        RUSTFLAGS: '-C target-feature=+atomics,+bulk-memory,+mutable-globals',
+      },
+      tmpThreadsBuildDir,
+      ['--features', 'parallel', '-Z', 'build-std=panic_abort,std']
+    );
+    emptyDir(distThreadsDir);
+    await copyDir(config, tmpThreadsBuildDir, distThreadsDir);
+  }
+
   console.log('🐻 wasm binding');
 }
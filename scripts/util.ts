@@ -62,6 +62,7 @@ const booleanOptions = [
   'tscDocs',
   'validate',
   'wasm',
+  'wasmThreads',
   'watch',
 ] as const;
 